@@ -4,18 +4,442 @@ use ink_lang as ink;
 
 #[ink::contract]
 mod erc20 {
-    use ink_storage::{collections::HashMap, lazy::Lazy};
+    use ink_env::{
+        call::{build_call, utils::ReturnType, ExecutionInput, Selector},
+        hash::Blake2x256,
+    };
+    use ink_prelude::{format, string::String, vec::Vec};
+    use ink_storage::{
+        collections::HashMap,
+        lazy::Lazy,
+        traits::{PackedLayout, SpreadLayout},
+    };
 
     #[ink(storage)]
     pub struct Erc20 {
         /// total token supply
         total_supply: Lazy<Balance>,
-        /// HahsMap account owner => owned token amount
+        /// HahsMap account owner => owned token amount, as a reflected
+        /// balance unless the owner is excluded from reflections
         balances: HashMap<AccountId, Balance>,
         /// HashMap (owner, spender) => allowed token amount
         /// spender is allowed to withdraw such amount of tokens from
         /// owner's account
         allowances: HashMap<(AccountId, AccountId), Balance>,
+        /// account allowed to tune the governed parameters
+        governor: Lazy<AccountId>,
+        /// account receiving the transfer fees
+        fee_recipient: Lazy<AccountId>,
+        /// transfer fee in basis points, deducted from the transferred value
+        fee_bps: Lazy<u16>,
+        /// share of the transferred value burned in basis points
+        burn_bps: Lazy<u16>,
+        /// lowest fee charged while a percentage fee is set
+        min_fee: Lazy<Balance>,
+        /// max token amount of a single transfer, 0 means no limit
+        max_tx_amount: Lazy<Balance>,
+        /// number of blocks a sender has to wait between two transfers
+        cooldown_blocks: Lazy<BlockNumber>,
+        /// HashMap account => block number of its last outgoing transfer
+        last_transfer_block: HashMap<AccountId, BlockNumber>,
+        /// HashMap claim id => (sender, recipient, escrowed token amount)
+        claims: HashMap<u64, (AccountId, AccountId, Balance)>,
+        /// id assigned to the next created claim
+        next_claim_id: Lazy<u64>,
+        /// account allowed to mint new tokens
+        owner: Lazy<AccountId>,
+        /// max total supply at deployment, before any decay
+        cap: Lazy<Balance>,
+        /// amount the cap shrinks by each elapsed decay window
+        cap_decay_per_window: Lazy<Balance>,
+        /// length of a cap decay window in blocks, 0 disables the decay
+        decay_window_blocks: Lazy<BlockNumber>,
+        /// block number the cap decay is counted from
+        cap_decay_start: Lazy<BlockNumber>,
+        /// HashMap account => staked token amount, not part of its balance
+        staked: HashMap<AccountId, Balance>,
+        /// sum of all staked token amounts
+        total_staked: Lazy<Balance>,
+        /// HashMap beneficiary => its vesting schedule
+        vestings: HashMap<AccountId, VestingSchedule>,
+        /// HashMap (payer, service) => payments the service may pull
+        subscriptions: HashMap<(AccountId, AccountId), Subscription>,
+        /// contract whose `trigger()` is called once enough tokens were burned
+        buyback_contract: Lazy<Option<AccountId>>,
+        /// burned token amount triggering a buyback, 0 disables it
+        buyback_threshold: Lazy<Balance>,
+        /// token amount burned since the last successful buyback trigger
+        burned_since_buyback: Lazy<Balance>,
+        /// max token amount transferred in a volume window, 0 means no limit
+        max_volume_per_window: Lazy<Balance>,
+        /// length of a volume window in blocks
+        volume_window_blocks: Lazy<BlockNumber>,
+        /// token amount transferred in the current volume window
+        volume_this_window: Lazy<Balance>,
+        /// block number the current volume window started at
+        window_start: Lazy<BlockNumber>,
+        /// while true only whitelisted accounts can send and receive tokens
+        whitelist_mode: Lazy<bool>,
+        /// HashMap account => () for the accounts allowed in whitelist mode
+        transfer_whitelist: HashMap<AccountId, ()>,
+        /// HashMap account => block number its sanction ends at,
+        /// `BlockNumber::MAX` for a permanent one
+        blacklist: HashMap<AccountId, BlockNumber>,
+        /// contracts whose `notify_transfer()` is called after every transfer
+        observers: Lazy<Vec<AccountId>>,
+        /// root of the Merkle tree of (account, amount) airdrop leaves
+        merkle_root: Lazy<Option<[u8; 32]>>,
+        /// HashMap account => () for the accounts which claimed their airdrop
+        merkle_claimed: HashMap<AccountId, ()>,
+        /// token amount set aside for the airdrop claims
+        airdrop_pool: Lazy<Balance>,
+        /// whether transfers are halted
+        paused: Lazy<bool>,
+        /// block number a scheduled pause takes effect at
+        scheduled_pause: Lazy<Option<BlockNumber>>,
+        /// whether the owner may still mint and burn while paused
+        admin_ops_during_pause: Lazy<bool>,
+        /// whether the fee recipient may still send tokens while paused
+        treasury_ops_during_pause: Lazy<bool>,
+        /// HashMap owner => the spenders it granted an allowance to
+        owner_spenders: HashMap<AccountId, Vec<AccountId>>,
+        /// whether emptying an account clears all the allowances it granted
+        clear_allowances_on_empty: Lazy<bool>,
+        /// HashMap (role, account) => () for the granted roles
+        roles: HashMap<(Role, AccountId), ()>,
+        /// HashMap burner => token amount it may still burn from the treasury
+        burn_quotas: HashMap<AccountId, Balance>,
+        /// (block number, total supply) after each of the latest mints and burns,
+        /// oldest first
+        supply_history: Lazy<Vec<(BlockNumber, Balance)>>,
+        /// HashMap index => (block number, total supply at the end of it),
+        /// one checkpoint per block the total supply changed in, oldest first
+        supply_checkpoints: HashMap<u32, (BlockNumber, Balance)>,
+        /// number of total supply checkpoints
+        supply_checkpoint_count: Lazy<u32>,
+        /// HashMap (sender, idempotency key) => () for the processed transfers
+        processed_keys: HashMap<(AccountId, [u8; 32]), ()>,
+        /// whether fractional fees are rounded up or down
+        fee_rounding: Lazy<RoundingMode>,
+        /// last block airdrop claims are accepted at, 0 for no deadline
+        claim_deadline: Lazy<BlockNumber>,
+        /// contract whose `redeem()` covers the shortfall of `transfer_with_vault()`
+        vault: Lazy<Option<AccountId>>,
+        /// whether holders may still burn their tokens while paused
+        allow_burn_during_pause: Lazy<bool>,
+        /// DEX router whose `swap_tokens_for_native()` sells the treasury's fees
+        router: Lazy<Option<AccountId>>,
+        /// treasury balance triggering a fee swap, 0 disables the swaps
+        swap_threshold: Lazy<Balance>,
+        /// whether a fee swap is running, so its own transfers don't swap again
+        in_swap: Lazy<bool>,
+        /// HashMap account => () for the AMM pairs, whose transfers never swap
+        amm_pairs: HashMap<AccountId, ()>,
+        /// block trading was enabled at, None before enable_trading()
+        launch_block: Lazy<Option<BlockNumber>>,
+        /// blocks after launch the sniper tax decays over
+        sniper_tax_blocks: Lazy<BlockNumber>,
+        /// sniper tax at the launch block in basis points, on top of the fee
+        sniper_tax_bps: Lazy<u16>,
+        /// whether batch transfers emit one `BatchTransfer` event
+        /// instead of a `Transfer` event per recipient
+        batch_events: Lazy<bool>,
+        /// account allowed to propose a new owner if the owner key is lost
+        recovery_guardian: Lazy<Option<AccountId>>,
+        /// blocks the owner has to cancel a proposed recovery
+        recovery_delay_blocks: Lazy<BlockNumber>,
+        /// proposed new owner and the block the recovery can be executed at
+        pending_recovery: Lazy<Option<(AccountId, BlockNumber)>>,
+        /// share of the transfer fee and sniper tax burned in basis points,
+        /// the rest goes to the fee recipient
+        tax_burn_share_bps: Lazy<u16>,
+        /// HashMap (owner, spender) => cap on what spender may spend per period
+        spending_limits: HashMap<(AccountId, AccountId), SpendingLimit>,
+        /// number of entries of the main storage maps
+        storage_stats: Lazy<StorageStats>,
+        /// HashMap (owner, spender) => how spender's allowance refills over time
+        regenerations: HashMap<(AccountId, AccountId), AllowanceRegeneration>,
+        /// max number of recipients of a batch transfer
+        max_batch_size: Lazy<u32>,
+        /// account which deployed the contract
+        deployer: Lazy<AccountId>,
+        /// HashMap account => block before which it can't send its tokens
+        unlock_blocks: HashMap<AccountId, BlockNumber>,
+        /// number of decimals of the token
+        decimals: Lazy<u8>,
+        /// number of fractional digits shown by balance_of_formatted(),
+        /// all the decimals if None
+        display_decimals: Lazy<Option<u8>>,
+        /// token backing this one, whose holdings are redeemable pro rata
+        backing_token: Lazy<Option<AccountId>>,
+        /// whether a redemption is in progress
+        in_redeem: Lazy<bool>,
+        /// HashMap account => number of transfers it sent
+        transfer_counts: HashMap<AccountId, u32>,
+        /// number of first transfers of an account which pay no fee
+        free_transfers_for_new: Lazy<u32>,
+        /// max allowance of a single approval besides unlimited ones,
+        /// 0 means no limit
+        max_single_approval: Lazy<Balance>,
+        /// max number of distinct spenders an account can approve
+        max_spenders_per_owner: Lazy<u32>,
+        /// HashMap (owner, spender) => how spender's allowance decays over time
+        decays: HashMap<(AccountId, AccountId), AllowanceDecay>,
+        /// whether recipients have to opt in before receiving transfers
+        require_opt_in: Lazy<bool>,
+        /// HashMap account => () for the accounts which opted in
+        opted_in: HashMap<AccountId, ()>,
+        /// (start block, end block) of the scheduled windows without transfer fees
+        fee_holidays: Lazy<Vec<(BlockNumber, BlockNumber)>>,
+        /// (min transferred value, fee in basis points) of the fee brackets,
+        /// ascending, replacing the flat fee from their min value on
+        fee_brackets: Lazy<Vec<(Balance, u16)>>,
+        /// account allowed to adjust the total supply
+        oracle: Lazy<Option<AccountId>>,
+        /// max supply adjustment of a single oracle call in basis points
+        /// of the total supply
+        max_adjustment_bps: Lazy<u16>,
+        /// HashMap account => (daily outbound limit, token amount sent today,
+        /// block number the day started at)
+        daily_limits: HashMap<AccountId, (Balance, Balance, BlockNumber)>,
+        /// HashMap account => (raised daily limit, block number it applies from)
+        pending_daily_limits: HashMap<AccountId, (Balance, BlockNumber)>,
+        /// namespace of the deployment, a topic of its `Transfer` events
+        topic_namespace: Lazy<[u8; 8]>,
+        /// whether spenders have to accept an owner's approvals before spending
+        require_spender_acceptance: Lazy<bool>,
+        /// HashMap (owner, spender) => () for the spenders which accepted to
+        /// spend owner's tokens
+        spender_accepted: HashMap<(AccountId, AccountId), ()>,
+        /// HashMap account => () for the dead addresses whose received tokens
+        /// are burned
+        burn_addresses: HashMap<AccountId, ()>,
+        /// salt the contract was instantiated with
+        deploy_salt: Lazy<[u8; 32]>,
+        /// HashMap account => (guardian able to move its tokens, blocks of
+        /// inactivity after which it can)
+        account_guardians: HashMap<AccountId, (AccountId, BlockNumber)>,
+        /// HashMap account => block number of its last transfer, approval or
+        /// guardian change, only for the accounts with a guardian
+        last_activity_blocks: HashMap<AccountId, BlockNumber>,
+        /// number of distinct minter approvals executing a mint proposal
+        mint_threshold: Lazy<u32>,
+        /// HashMap proposal id => mint proposal waiting for approvals
+        mint_proposals: HashMap<u64, MintProposal>,
+        /// HashMap proposal id => minters who approved the pending proposal
+        mint_approvals: HashMap<u64, Vec<AccountId>>,
+        /// id the next mint proposal gets
+        next_mint_proposal_id: Lazy<u64>,
+        /// id the next invoiced transfer gets
+        next_invoice_id: Lazy<u64>,
+        /// token this one wraps, None if wrapping is disabled
+        wrapped_token: Lazy<Option<AccountId>>,
+        /// number of decimals of the wrapped token
+        underlying_decimals: Lazy<u8>,
+        /// whether staking and unstaking are halted, independently of pause()
+        staking_paused: Lazy<bool>,
+        /// v2 token amount minted per converted token, 0 disables convert()
+        conversion_ratio: Lazy<Balance>,
+        /// total supply of the v2 token
+        v2_total_supply: Lazy<Balance>,
+        /// HashMap account => v2 token balance
+        v2_balances: HashMap<AccountId, Balance>,
+        /// HashMap id => time-locked transfer waiting for execution
+        queued_transfers: HashMap<u64, QueuedTransfer>,
+        /// id the next queued transfer gets
+        next_queued_transfer_id: Lazy<u64>,
+        /// number of blocks after `execute_after` a queued transfer can be
+        /// executed within, 0 for no expiry
+        execution_window_blocks: Lazy<BlockNumber>,
+        /// number of blocks a parameter change waits before it can be
+        /// applied, at least the min parameter delay
+        parameter_delay_blocks: Lazy<BlockNumber>,
+        /// HashMap parameter => (queued value, block it can be applied from)
+        pending_parameters: HashMap<ParamKey, (u128, BlockNumber)>,
+        /// share of every transfer redistributed to the holders in basis points
+        reflection_bps: Lazy<u16>,
+        /// sum of the reflected balances of the accounts not excluded from
+        /// reflections
+        reflected_supply: Lazy<Balance>,
+        /// token amount the reflected supply is worth, growing with every
+        /// reflection
+        reflected_tokens: Lazy<Balance>,
+        /// HashMap account => () for the accounts excluded from reflections
+        reflection_excluded: HashMap<AccountId, ()>,
+        /// whether a message making external calls is running, so they
+        /// can't enter the contract again
+        entered: Lazy<bool>,
+        /// contract being called that may enter the contract again, the
+        /// router of a fee swap or the spender of approve_call_before()
+        reentrant_callee: Lazy<Option<AccountId>>,
+        /// HashMap account => () for the callers allowed to skip the
+        /// reentrancy guard through unchecked_transfer()
+        trusted_callers: HashMap<AccountId, ()>,
+    }
+
+    /// recurring payment a service may pull from a payer
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Subscription {
+        /// token amount due every interval
+        pub amount_per_interval: Balance,
+        /// length of an interval in blocks
+        pub interval_blocks: BlockNumber,
+        /// block number the last paid interval ended at
+        pub last_pull_block: BlockNumber,
+    }
+
+    /// tokens unlocking linearly for a beneficiary
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct VestingSchedule {
+        /// total token amount vested
+        pub total: Balance,
+        /// token amount already released to the beneficiary
+        pub released: Balance,
+        /// block number the vesting starts at
+        pub start: BlockNumber,
+        /// number of blocks until everything is vested
+        pub duration: BlockNumber,
+        /// whether the owner may revoke the unvested tokens
+        pub revocable: bool,
+    }
+
+    /// cap on the token amount a spender may spend of its allowance per period
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct SpendingLimit {
+        /// max token amount spent per period
+        pub per_period_limit: Balance,
+        /// number of blocks of a period
+        pub period_blocks: BlockNumber,
+        /// token amount spent in the current period
+        pub spent_this_period: Balance,
+        /// block number the current period started at
+        pub period_start: BlockNumber,
+    }
+
+    /// refill rate of an allowance
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct AllowanceRegeneration {
+        /// allowance the refill stops at
+        pub max: Balance,
+        /// token amount added to the allowance every block
+        pub regen_per_block: Balance,
+        /// block number the stored allowance was last written at
+        pub last_update: BlockNumber,
+    }
+
+    /// linear decay of an allowance to zero
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct AllowanceDecay {
+        /// approved token amount the decay starts from
+        pub value: Balance,
+        /// block number of the approval
+        pub start: BlockNumber,
+        /// number of blocks until the allowance reaches zero
+        pub decay_blocks: BlockNumber,
+    }
+
+    /// an allowance with the records shaping it, taken by approve_call_before()
+    /// to restore it as it was if the spender call fails
+    struct AllowanceSnapshot {
+        allowance: Option<Balance>,
+        spending_limit: Option<SpendingLimit>,
+        regeneration: Option<AllowanceRegeneration>,
+        decay: Option<AllowanceDecay>,
+        spenders: Option<Vec<AccountId>>,
+    }
+
+    /// mint proposed by a minter, executed once enough minters approve it
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct MintProposal {
+        /// account receiving the minted tokens
+        pub to: AccountId,
+        /// token amount to mint
+        pub amount: Balance,
+        /// number of distinct minters approving so far, the proposer included
+        pub approvals: u32,
+    }
+
+    /// tokens escrowed for a transfer executable from a block on
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct QueuedTransfer {
+        /// account the tokens were escrowed from
+        pub from: AccountId,
+        /// account receiving the tokens
+        pub to: AccountId,
+        /// escrowed token amount
+        pub value: Balance,
+        /// first block the transfer can be executed at
+        pub execute_after: BlockNumber,
+    }
+
+    /// number of entries of the main storage maps, to estimate the storage deposit
+    #[derive(
+        Debug,
+        Default,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        scale::Encode,
+        scale::Decode,
+        SpreadLayout,
+        PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct StorageStats {
+        /// accounts with a non-zero balance
+        pub holder_count: u32,
+        /// non-zero allowances
+        pub allowance_count: u32,
+        /// blacklisted accounts, including the expired sanctions
+        pub blacklist_count: u32,
+        /// vesting schedules
+        pub vesting_count: u32,
     }
 
     #[ink(event)]
@@ -24,6 +448,8 @@ mod erc20 {
         from: Option<AccountId>,
         to: Option<AccountId>,
         value: Balance,
+        #[ink(topic)]
+        namespace: [u8; 8],
     }
 
     #[ink(event)]
@@ -35,33 +461,753 @@ mod erc20 {
         value: Balance,
     }
 
+    #[ink(event)]
+    pub struct ApprovalRevoked {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct SpenderLabeled {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        label: [u8; 16],
+    }
+
+    #[ink(event)]
+    pub struct Staked {
+        #[ink(topic)]
+        who: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Unstaked {
+        #[ink(topic)]
+        who: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct VestingReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Reflected {
+        #[ink(topic)]
+        from: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BuybackTriggered {
+        #[ink(topic)]
+        contract: AccountId,
+        burned: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BuybackFailed {
+        #[ink(topic)]
+        contract: AccountId,
+        burned: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AirdropClaimed {
+        #[ink(topic)]
+        who: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct FeeRecipientChanged {
+        #[ink(topic)]
+        old: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BatchTransfer {
+        #[ink(topic)]
+        from: AccountId,
+        total: Balance,
+        count: u32,
+    }
+
+    #[ink(event)]
+    pub struct PauseScheduled {
+        at_block: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct SupplyAdjusted {
+        from: Balance,
+        to: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MintProposed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        proposer: AccountId,
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MintApproved {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        minter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct MintExecuted {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    #[ink(event)]
+    pub struct Invoice {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+        block: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct Converted {
+        #[ink(topic)]
+        who: AccountId,
+        burned: Balance,
+        minted: Balance,
+    }
+
+    #[ink(event)]
+    pub struct VestingRevoked {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        returned: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LocksSwept {
+        count: u32,
+    }
+
+    #[ink(event)]
+    pub struct ParameterChanged {
+        key: ParamKey,
+        old: u128,
+        new: u128,
+    }
+
+    #[ink(event)]
+    pub struct ParameterQueued {
+        key: ParamKey,
+        value: u128,
+        effective_block: BlockNumber,
+    }
+
+    /// parameters which can only be tuned by the governor
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        scale::Encode,
+        scale::Decode,
+        SpreadLayout,
+        PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum ParamKey {
+        /// transfer fee in basis points, at most 10000
+        FeeBps,
+        /// max token amount of a single transfer, 0 disables the limit
+        MaxTxAmount,
+        /// blocks to wait between two transfers of the same sender
+        CooldownBlocks,
+        /// share of every transfer burned in basis points, at most 10000
+        BurnBps,
+        /// lowest fee charged by a non-zero percentage fee
+        MinFee,
+        /// share of the transfer fee and tax burned in basis points, at most 10000
+        TaxBurnShareBps,
+        /// share of every transfer redistributed to the holders in basis
+        /// points, at most 10000
+        ReflectionBps,
+    }
+
+    /// which side a fractional fee is rounded in favor of
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum RoundingMode {
+        /// round fees up
+        TowardTreasury,
+        /// round fees down
+        TowardSender,
+    }
+
+    /// optional module of the token, for tooling to adapt to a deployment
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum FeatureFlag {
+        /// transfers are charged a fee or partly burned
+        Fees,
+        /// the owner can pause transfers
+        Pausable,
+        /// tokens can be staked
+        Staking,
+        /// tokens can vest to a beneficiary
+        Vesting,
+        /// only whitelisted accounts can transfer
+        Whitelist,
+        /// senders wait between two transfers
+        Cooldown,
+        /// collected fees are swapped through a router
+        FeeSwap,
+    }
+
+    /// how a transferred value is split up
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TransferPreview {
+        /// token amount credited to the recipient
+        pub net_to_recipient: Balance,
+        /// token amount paid to the fee recipient
+        pub fee: Balance,
+        /// token amount burned, including the burned share of the fee and tax
+        pub burn: Balance,
+        /// token amount paid to the fee recipient as tax, on top of the fee
+        pub tax: Balance,
+        /// token amount redistributed to the holders not excluded from reflections
+        pub reflection: Balance,
+    }
+
     #[derive(Debug, PartialEq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         InsufficientBalance,
         InsufficientApproval,
+        NotGovernor,
+        InvalidParameter,
+        MaxTxExceeded,
+        CooldownActive,
+        ClaimNotFound,
+        NotClaimRecipient,
+        NotClaimSender,
+        NotOwner,
+        CapExceeded,
+        InsufficientStake,
+        VestingExists,
+        NoVesting,
+        NoSubscription,
+        IntervalNotElapsed,
+        DuplicateRecipient,
+        Overflow,
+        VolumeCapExceeded,
+        NotWhitelisted,
+        Blacklisted,
+        TooManyObservers,
+        NoMerkleRoot,
+        InvalidProof,
+        AlreadyClaimed,
+        InsufficientAirdropPool,
+        InvalidSplit,
+        Paused,
+        SlippageExceeded,
+        MissingRole,
+        BurnerQuotaExceeded,
+        DeadlinePassed,
+        CallFailed,
+        DuplicateRequest,
+        ClaimPeriodEnded,
+        ClaimPeriodActive,
+        VaultRedeemFailed,
+        TradingAlreadyEnabled,
+        NotGuardian,
+        NoPendingRecovery,
+        RecoveryDelayActive,
+        PeriodLimitExceeded,
+        BatchTooLarge,
+        TokensLocked,
+        RedeemFailed,
+        ApprovalTooLarge,
+        RecipientNotOptedIn,
+        NotOracle,
+        DailyLimitExceeded,
+        SpenderNotAccepted,
+        TooManySpenders,
+        VestingNotRevocable,
+        MintProposalNotFound,
+        AlreadyApproved,
+        WrapFailed,
+        StakingPaused,
+        ConversionDisabled,
+        QueuedTransferNotFound,
+        TimelockActive,
+        TransferExpired,
+        TransferNotExpired,
+        NoPendingParameter,
+        Reentrancy,
+        NotTrusted,
+    }
+
+    impl Error {
+        /// stable numeric code of the error for off-chain tooling
+        pub fn code(&self) -> u16 {
+            match self {
+                Error::InsufficientBalance => 1,
+                Error::InsufficientApproval => 2,
+                Error::NotGovernor => 3,
+                Error::InvalidParameter => 4,
+                Error::MaxTxExceeded => 5,
+                Error::CooldownActive => 6,
+                Error::ClaimNotFound => 7,
+                Error::NotClaimRecipient => 8,
+                Error::NotClaimSender => 9,
+                Error::NotOwner => 10,
+                Error::CapExceeded => 11,
+                Error::InsufficientStake => 12,
+                Error::VestingExists => 13,
+                Error::NoVesting => 14,
+                Error::NoSubscription => 15,
+                Error::IntervalNotElapsed => 16,
+                Error::DuplicateRecipient => 17,
+                Error::Overflow => 18,
+                Error::VolumeCapExceeded => 19,
+                Error::NotWhitelisted => 20,
+                Error::Blacklisted => 21,
+                Error::TooManyObservers => 22,
+                Error::NoMerkleRoot => 23,
+                Error::InvalidProof => 24,
+                Error::AlreadyClaimed => 25,
+                Error::InsufficientAirdropPool => 26,
+                Error::InvalidSplit => 27,
+                Error::Paused => 28,
+                Error::SlippageExceeded => 29,
+                Error::MissingRole => 30,
+                Error::BurnerQuotaExceeded => 31,
+                Error::DeadlinePassed => 32,
+                Error::CallFailed => 33,
+                Error::DuplicateRequest => 34,
+                Error::ClaimPeriodEnded => 35,
+                Error::ClaimPeriodActive => 36,
+                Error::VaultRedeemFailed => 37,
+                Error::TradingAlreadyEnabled => 38,
+                Error::NotGuardian => 39,
+                Error::NoPendingRecovery => 40,
+                Error::RecoveryDelayActive => 41,
+                Error::PeriodLimitExceeded => 42,
+                Error::BatchTooLarge => 43,
+                Error::TokensLocked => 44,
+                Error::RedeemFailed => 45,
+                Error::ApprovalTooLarge => 46,
+                Error::RecipientNotOptedIn => 47,
+                Error::NotOracle => 48,
+                Error::DailyLimitExceeded => 49,
+                Error::SpenderNotAccepted => 50,
+                Error::TooManySpenders => 51,
+                Error::VestingNotRevocable => 52,
+                Error::MintProposalNotFound => 53,
+                Error::AlreadyApproved => 54,
+                Error::WrapFailed => 55,
+                Error::StakingPaused => 56,
+                Error::ConversionDisabled => 57,
+                Error::QueuedTransferNotFound => 58,
+                Error::TimelockActive => 59,
+                Error::TransferExpired => 60,
+                Error::TransferNotExpired => 61,
+                Error::NoPendingParameter => 62,
+                Error::Reentrancy => 63,
+                Error::NotTrusted => 64,
+            }
+        }
+
+        /// short human readable message of the error
+        #[cfg(feature = "std")]
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Error::InsufficientBalance => "insufficient balance",
+                Error::InsufficientApproval => "insufficient allowance",
+                Error::NotGovernor => "caller is not the governor",
+                Error::InvalidParameter => "invalid parameter",
+                Error::MaxTxExceeded => "max transaction amount exceeded",
+                Error::CooldownActive => "transfer cooldown active",
+                Error::ClaimNotFound => "claim not found",
+                Error::NotClaimRecipient => "caller is not the claim recipient",
+                Error::NotClaimSender => "caller is not the claim sender",
+                Error::NotOwner => "caller is not the owner",
+                Error::CapExceeded => "supply cap exceeded",
+                Error::InsufficientStake => "insufficient stake",
+                Error::VestingExists => "vesting already exists",
+                Error::NoVesting => "no vesting schedule",
+                Error::NoSubscription => "no subscription",
+                Error::IntervalNotElapsed => "payment interval not elapsed",
+                Error::DuplicateRecipient => "duplicate recipient",
+                Error::Overflow => "arithmetic overflow",
+                Error::VolumeCapExceeded => "volume cap exceeded",
+                Error::NotWhitelisted => "account not whitelisted",
+                Error::Blacklisted => "account blacklisted",
+                Error::TooManyObservers => "too many observers",
+                Error::NoMerkleRoot => "no merkle root",
+                Error::InvalidProof => "invalid merkle proof",
+                Error::AlreadyClaimed => "airdrop already claimed",
+                Error::InsufficientAirdropPool => "insufficient airdrop pool",
+                Error::InvalidSplit => "split does not sum up to 100%",
+                Error::Paused => "contract paused",
+                Error::SlippageExceeded => "received amount below minimum",
+                Error::MissingRole => "caller is missing the role",
+                Error::BurnerQuotaExceeded => "burn quota exceeded",
+                Error::DeadlinePassed => "deadline passed",
+                Error::CallFailed => "contract call failed",
+                Error::DuplicateRequest => "duplicate request",
+                Error::ClaimPeriodEnded => "claim period ended",
+                Error::ClaimPeriodActive => "claim period still active",
+                Error::VaultRedeemFailed => "vault redeem failed",
+                Error::TradingAlreadyEnabled => "trading already enabled",
+                Error::NotGuardian => "caller is not the recovery guardian",
+                Error::NoPendingRecovery => "no pending recovery",
+                Error::RecoveryDelayActive => "recovery delay active",
+                Error::PeriodLimitExceeded => "period spending limit exceeded",
+                Error::BatchTooLarge => "batch too large",
+                Error::TokensLocked => "tokens locked",
+                Error::RedeemFailed => "redemption failed",
+                Error::ApprovalTooLarge => "approval too large",
+                Error::RecipientNotOptedIn => "recipient not opted in",
+                Error::NotOracle => "caller is not the oracle",
+                Error::DailyLimitExceeded => "daily limit exceeded",
+                Error::SpenderNotAccepted => "spender not accepted",
+                Error::TooManySpenders => "too many spenders",
+                Error::VestingNotRevocable => "vesting not revocable",
+                Error::MintProposalNotFound => "mint proposal not found",
+                Error::AlreadyApproved => "already approved",
+                Error::WrapFailed => "wrap failed",
+                Error::StakingPaused => "staking paused",
+                Error::ConversionDisabled => "conversion disabled",
+                Error::QueuedTransferNotFound => "queued transfer not found",
+                Error::TimelockActive => "timelock active",
+                Error::TransferExpired => "transfer expired",
+                Error::TransferNotExpired => "transfer not expired",
+                Error::NoPendingParameter => "no pending parameter",
+                Error::Reentrancy => "reentrant call",
+                Error::NotTrusted => "caller is not trusted",
+            }
+        }
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
+    /// identifier of a role granted by the owner
+    pub type Role = u32;
+
+    /// role allowed to burn from the treasury up to its quota
+    pub const BURNER_ROLE: Role = 1;
+
+    /// role allowed to propose and approve mints
+    pub const MINTER_ROLE: Role = 2;
+
     impl Erc20 {
-        /// init the contract with the initial total supply
+        /// init the contract with the initial total supply,
+        /// the caller becomes the governor
         #[ink(constructor)]
         pub fn new(supply: Balance) -> Self {
+            Self::new_with_governor(supply, Self::env().caller())
+        }
+
+        /// init the contract with the initial total supply and a max supply
+        /// `cap` shrinking by `cap_decay_per_window` every `decay_window_blocks`
+        #[ink(constructor)]
+        pub fn new_with_cap(
+            supply: Balance,
+            cap: Balance,
+            cap_decay_per_window: Balance,
+            decay_window_blocks: BlockNumber,
+        ) -> Self {
+            assert!(supply <= cap, "initial supply exceeds the cap");
+            let mut erc20 = Self::new_with_governor(supply, Self::env().caller());
+            Lazy::set(&mut erc20.cap, cap);
+            Lazy::set(&mut erc20.cap_decay_per_window, cap_decay_per_window);
+            Lazy::set(&mut erc20.decay_window_blocks, decay_window_blocks);
+            // store the initial supply again at the rate the cap allows
+            let caller = Self::env().caller();
+            erc20.set_balance(caller, 0);
+            erc20.set_balance(caller, supply);
+            erc20
+        }
+
+        /// init the contract with the initial total supply and fractional fees
+        /// rounded as `fee_rounding` says, instead of down by default
+        #[ink(constructor)]
+        pub fn new_with_fee_rounding(supply: Balance, fee_rounding: RoundingMode) -> Self {
+            let mut erc20 = Self::new_with_governor(supply, Self::env().caller());
+            Lazy::set(&mut erc20.fee_rounding, fee_rounding);
+            erc20
+        }
+
+        /// init the contract with the initial total supply and record the
+        /// `deploy_salt` it is instantiated with, so off-chain tools can
+        /// check which salt produced its address
+        #[ink(constructor)]
+        pub fn new_with_salt(supply: Balance, deploy_salt: [u8; 32]) -> Self {
+            let mut erc20 = Self::new_with_governor(supply, Self::env().caller());
+            Lazy::set(&mut erc20.deploy_salt, deploy_salt);
+            erc20
+        }
+
+        /// getter for the salt the contract was instantiated with,
+        /// all zeros if not set at construction
+        #[ink(message)]
+        pub fn deploy_salt(&self) -> [u8; 32] {
+            *self.deploy_salt
+        }
+
+        /// init the contract with the initial total supply and `topic_namespace`
+        /// as a topic of its `Transfer` events, so indexers can tell the
+        /// deployments apart
+        #[ink(constructor)]
+        pub fn new_with_namespace(supply: Balance, topic_namespace: [u8; 8]) -> Self {
+            let caller = Self::env().caller();
+            let mut erc20 = Self::init(caller);
+            Lazy::set(&mut erc20.topic_namespace, topic_namespace);
+            erc20.seed(caller, supply);
+            erc20
+        }
+
+        /// getter for the namespace of the deployment
+        #[ink(message)]
+        pub fn topic_namespace(&self) -> [u8; 8] {
+            *self.topic_namespace
+        }
+
+        /// init the contract with the initial total supply of a token
+        /// with `decimals` decimals
+        ///
+        /// panic if `decimals` is above 38, whose unit wouldn't fit a balance
+        #[ink(constructor)]
+        pub fn new_with_decimals(supply: Balance, decimals: u8) -> Self {
+            assert!(decimals <= MAX_DECIMALS, "too many decimals");
+            let mut erc20 = Self::new_with_governor(supply, Self::env().caller());
+            Lazy::set(&mut erc20.decimals, decimals);
+            erc20
+        }
+
+        /// init the contract with the initial total supply, which the deployer
+        /// can't send before `lock_blocks` blocks passed
+        #[ink(constructor)]
+        pub fn new_with_lock(supply: Balance, lock_blocks: BlockNumber) -> Self {
             let caller = Self::env().caller();
-            let mut balances = HashMap::new();
-            balances.insert(caller, supply);
+            let mut erc20 = Self::new_with_governor(supply, caller);
+            let unlock_block = Self::env().block_number().saturating_add(lock_blocks);
+            erc20.unlock_blocks.insert(caller, unlock_block);
+            erc20
+        }
+
+        /// init the contract with the initial total supply and
+        /// `governor` as the account allowed to tune parameters
+        #[ink(constructor)]
+        pub fn new_with_governor(supply: Balance, governor: AccountId) -> Self {
+            let mut erc20 = Self::init(governor);
+            erc20.seed(Self::env().caller(), supply);
+            erc20
+        }
+
+        /// init the contract with the initial supply distributed to several accounts,
+        /// the total supply is the sum of all `allocations`
+        ///
+        /// panic with DuplicateRecipient if an account appears twice
+        /// or Overflow if the allocations sum up above the max balance
+        #[ink(constructor)]
+        pub fn new_with_allocations(allocations: Vec<(AccountId, Balance)>) -> Self {
+            if let Err(error) = Self::validate_allocations(&allocations) {
+                panic!("invalid allocations: {:?}", error);
+            }
+            let mut erc20 = Self::init(Self::env().caller());
+            for (to, value) in allocations {
+                erc20.seed(to, value);
+            }
+            erc20
+        }
+
+        /// check the initial `allocations` have distinct accounts and a
+        /// representable sum, return that sum
+        fn validate_allocations(allocations: &[(AccountId, Balance)]) -> Result<Balance> {
+            let mut total: Balance = 0;
+            for (i, (to, value)) in allocations.iter().enumerate() {
+                if allocations[..i].iter().any(|(other, _)| other == to) {
+                    return Err(Error::DuplicateRecipient);
+                }
+                total = total.checked_add(*value).ok_or(Error::Overflow)?;
+            }
+            Ok(total)
+        }
 
+        /// credit the initial `value` tokens to `to` at construction
+        /// emit `Transfer` event
+        fn seed(&mut self, to: AccountId, value: Balance) {
+            let to_balance = self.balance_of(to);
+            self.set_balance(to, to_balance + value);
+            let total_supply = *self.total_supply + value;
+            Lazy::set(&mut self.total_supply, total_supply);
+            self.write_supply_checkpoint(total_supply);
             Self::env().emit_event(Transfer {
                 from: None,
-                to: Some(caller),
-                value: supply,
+                to: Some(to),
+                value,
+                namespace: *self.topic_namespace,
             });
+        }
 
+        /// build an empty contract, the caller is the owner and fee recipient
+        fn init(governor: AccountId) -> Self {
+            let caller = Self::env().caller();
             Self {
-                total_supply: Lazy::new(supply),
-                balances,
+                total_supply: Lazy::new(0),
+                balances: HashMap::new(),
                 allowances: HashMap::new(),
+                governor: Lazy::new(governor),
+                fee_recipient: Lazy::new(caller),
+                fee_bps: Lazy::new(0),
+                burn_bps: Lazy::new(0),
+                min_fee: Lazy::new(0),
+                max_tx_amount: Lazy::new(0),
+                cooldown_blocks: Lazy::new(0),
+                last_transfer_block: HashMap::new(),
+                claims: HashMap::new(),
+                next_claim_id: Lazy::new(0),
+                owner: Lazy::new(caller),
+                cap: Lazy::new(Balance::MAX),
+                cap_decay_per_window: Lazy::new(0),
+                decay_window_blocks: Lazy::new(0),
+                cap_decay_start: Lazy::new(Self::env().block_number()),
+                staked: HashMap::new(),
+                total_staked: Lazy::new(0),
+                vestings: HashMap::new(),
+                subscriptions: HashMap::new(),
+                buyback_contract: Lazy::new(None),
+                buyback_threshold: Lazy::new(0),
+                burned_since_buyback: Lazy::new(0),
+                max_volume_per_window: Lazy::new(0),
+                volume_window_blocks: Lazy::new(0),
+                volume_this_window: Lazy::new(0),
+                window_start: Lazy::new(0),
+                whitelist_mode: Lazy::new(false),
+                transfer_whitelist: HashMap::new(),
+                blacklist: HashMap::new(),
+                observers: Lazy::new(Vec::new()),
+                merkle_root: Lazy::new(None),
+                merkle_claimed: HashMap::new(),
+                airdrop_pool: Lazy::new(0),
+                paused: Lazy::new(false),
+                scheduled_pause: Lazy::new(None),
+                admin_ops_during_pause: Lazy::new(false),
+                treasury_ops_during_pause: Lazy::new(false),
+                owner_spenders: HashMap::new(),
+                clear_allowances_on_empty: Lazy::new(false),
+                roles: HashMap::new(),
+                burn_quotas: HashMap::new(),
+                supply_history: Lazy::new(Vec::new()),
+                supply_checkpoints: HashMap::new(),
+                supply_checkpoint_count: Lazy::new(0),
+                processed_keys: HashMap::new(),
+                fee_rounding: Lazy::new(RoundingMode::TowardSender),
+                claim_deadline: Lazy::new(0),
+                vault: Lazy::new(None),
+                allow_burn_during_pause: Lazy::new(false),
+                router: Lazy::new(None),
+                swap_threshold: Lazy::new(0),
+                in_swap: Lazy::new(false),
+                amm_pairs: HashMap::new(),
+                launch_block: Lazy::new(None),
+                sniper_tax_blocks: Lazy::new(0),
+                sniper_tax_bps: Lazy::new(0),
+                batch_events: Lazy::new(false),
+                recovery_guardian: Lazy::new(None),
+                recovery_delay_blocks: Lazy::new(0),
+                pending_recovery: Lazy::new(None),
+                tax_burn_share_bps: Lazy::new(0),
+                spending_limits: HashMap::new(),
+                storage_stats: Lazy::new(StorageStats::default()),
+                regenerations: HashMap::new(),
+                max_batch_size: Lazy::new(DEFAULT_MAX_BATCH_SIZE),
+                deployer: Lazy::new(caller),
+                unlock_blocks: HashMap::new(),
+                decimals: Lazy::new(DEFAULT_DECIMALS),
+                display_decimals: Lazy::new(None),
+                backing_token: Lazy::new(None),
+                in_redeem: Lazy::new(false),
+                transfer_counts: HashMap::new(),
+                free_transfers_for_new: Lazy::new(0),
+                max_single_approval: Lazy::new(0),
+                max_spenders_per_owner: Lazy::new(DEFAULT_MAX_SPENDERS_PER_OWNER),
+                decays: HashMap::new(),
+                require_opt_in: Lazy::new(false),
+                opted_in: HashMap::new(),
+                fee_holidays: Lazy::new(Vec::new()),
+                fee_brackets: Lazy::new(Vec::new()),
+                oracle: Lazy::new(None),
+                max_adjustment_bps: Lazy::new(0),
+                daily_limits: HashMap::new(),
+                pending_daily_limits: HashMap::new(),
+                topic_namespace: Lazy::new([0; 8]),
+                require_spender_acceptance: Lazy::new(false),
+                spender_accepted: HashMap::new(),
+                burn_addresses: HashMap::new(),
+                deploy_salt: Lazy::new([0; 32]),
+                account_guardians: HashMap::new(),
+                last_activity_blocks: HashMap::new(),
+                mint_threshold: Lazy::new(1),
+                mint_proposals: HashMap::new(),
+                mint_approvals: HashMap::new(),
+                next_mint_proposal_id: Lazy::new(0),
+                next_invoice_id: Lazy::new(0),
+                wrapped_token: Lazy::new(None),
+                underlying_decimals: Lazy::new(DEFAULT_DECIMALS),
+                staking_paused: Lazy::new(false),
+                conversion_ratio: Lazy::new(0),
+                v2_total_supply: Lazy::new(0),
+                v2_balances: HashMap::new(),
+                queued_transfers: HashMap::new(),
+                next_queued_transfer_id: Lazy::new(0),
+                execution_window_blocks: Lazy::new(0),
+                parameter_delay_blocks: Lazy::new(MIN_PARAMETER_DELAY_BLOCKS),
+                pending_parameters: HashMap::new(),
+                reflection_bps: Lazy::new(0),
+                reflected_supply: Lazy::new(0),
+                reflected_tokens: Lazy::new(0),
+                reflection_excluded: HashMap::new(),
+                entered: Lazy::new(false),
+                reentrant_callee: Lazy::new(None),
+                trusted_callers: HashMap::new(),
             }
         }
 
@@ -71,378 +1217,9328 @@ mod erc20 {
             *self.total_supply
         }
 
-        /// get the owned token amount for certain `who`
-        /// return 0 if account doesn't exist.
+        /// get up to `limit` entries of the supply history from the `start`th one,
+        /// each entry is the (block number, total supply) after a mint or burn
+        /// only the latest entries are kept
         #[ink(message)]
-        pub fn balance_of(&self, who: AccountId) -> Balance {
-            self.balances.get(&who).copied().unwrap_or(0)
+        pub fn supply_history_page(&self, start: u32, limit: u32) -> Vec<(BlockNumber, Balance)> {
+            self.supply_history
+                .iter()
+                .skip(start as usize)
+                .take(limit as usize)
+                .copied()
+                .collect()
         }
 
-        /// get the token amount which `spender` is allowed to withdraw
-        /// from `owner`'s account
-        /// return 0 if no allowance was set
+        /// update the total supply after a mint or burn and record it
+        /// in the supply history, dropping the oldest entry when full
+        fn set_total_supply(&mut self, total_supply: Balance) {
+            Lazy::set(&mut self.total_supply, total_supply);
+            let now = self.env().block_number();
+            let history = Lazy::get_mut(&mut self.supply_history);
+            if history.len() == MAX_SUPPLY_HISTORY {
+                history.remove(0);
+            }
+            history.push((now, total_supply));
+            self.write_supply_checkpoint(total_supply);
+        }
+
+        /// record `total_supply` as the total supply at the end of the current
+        /// block, overwriting the checkpoint of the block if any
+        fn write_supply_checkpoint(&mut self, total_supply: Balance) {
+            let now = self.env().block_number();
+            let count = *self.supply_checkpoint_count;
+            let last = count
+                .checked_sub(1)
+                .and_then(|i| self.supply_checkpoints.get(&i));
+            match last {
+                Some((block, _)) if *block == now => {
+                    self.supply_checkpoints
+                        .insert(count - 1, (now, total_supply));
+                }
+                _ => {
+                    self.supply_checkpoints.insert(count, (now, total_supply));
+                    Lazy::set(&mut self.supply_checkpoint_count, count + 1);
+                }
+            }
+        }
+
+        /// get the total supply at the end of `block`, to compute a quorum
+        /// as of a past snapshot block
+        ///
+        /// throw InvalidParameter if `block` isn't over yet
         #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
-            self.allowances.get(&(owner, spender)).copied().unwrap_or(0)
+        pub fn get_past_total_supply(&self, block: BlockNumber) -> Result<Balance> {
+            if block >= self.env().block_number() {
+                return Err(Error::InvalidParameter);
+            }
+            // binary search of the first checkpoint after `block`
+            let (mut low, mut high) = (0, *self.supply_checkpoint_count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                match self.supply_checkpoints.get(&mid) {
+                    Some((checkpoint_block, _)) if *checkpoint_block <= block => low = mid + 1,
+                    _ => high = mid,
+                }
+            }
+            Ok(low
+                .checked_sub(1)
+                .and_then(|i| self.supply_checkpoints.get(&i))
+                .map_or(0, |(_, total_supply)| *total_supply))
         }
 
-        /// transfer `value` amount of tokens from caller's account to `to`
-        /// call internal transfer func
+        /// get the current max total supply
+        /// the initial cap minus the decay of every elapsed window,
+        /// but never below the current total supply
         #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
-            let from = self.env().caller(); // same as Self::env().caller();
-            self.inner_transfer(from, to, value)
+        pub fn cap(&self) -> Balance {
+            let decay_window_blocks = *self.decay_window_blocks;
+            if decay_window_blocks == 0 {
+                return *self.cap;
+            }
+
+            let elapsed = self.env().block_number() - *self.cap_decay_start;
+            let windows = Balance::from(elapsed / decay_window_blocks);
+            let decay = windows.saturating_mul(*self.cap_decay_per_window);
+            self.cap.saturating_sub(decay).max(*self.total_supply)
         }
 
-        /// approve `to` to withdraw from caller's account for `value` token amount
-        /// emit `Approval` event
+        /// getter for the supply oracle and its max adjustment per call
+        /// in basis points of the total supply
         #[ink(message)]
-        pub fn approve(&mut self, to: AccountId, value: Balance) -> Result<()> {
-            let owner = self.env().caller();
-            self.allowances.insert((owner, to), value);
-            self.env().emit_event(Approval {
-                owner,
-                spender: to,
-                value,
-            });
-            Ok(())
+        pub fn oracle(&self) -> (Option<AccountId>, u16) {
+            (*self.oracle, *self.max_adjustment_bps)
         }
 
-        /// transfers `value` tokens on the behalf of `from` to the account `to`
+        /// let `oracle` adjust the total supply by at most `max_adjustment_bps`
+        /// basis points per call, only callable by the owner
         ///
-        /// The caller must be allowed to do so, that is:
-        /// self.allowance(from, caller) must exist and sufficient allowance must be provided
+        /// throw InvalidParameter if `max_adjustment_bps` is above 10000
         #[ink(message)]
-        pub fn transfer_from(
+        pub fn set_oracle(
             &mut self,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
+            oracle: Option<AccountId>,
+            max_adjustment_bps: u16,
         ) -> Result<()> {
+            self.ensure_owner()?;
+            if u128::from(max_adjustment_bps) > MAX_BPS {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.oracle, oracle);
+            Lazy::set(&mut self.max_adjustment_bps, max_adjustment_bps);
+            Ok(())
+        }
+
+        /// mint to or burn from the fee recipient to bring the total supply
+        /// to `target_supply`, only callable by the oracle
+        ///
+        /// throw NotOracle if the caller isn't the oracle
+        /// throw Paused while paused, unless admin ops are allowed during pause
+        /// throw InvalidParameter if the adjustment exceeds the max adjustment
+        /// throw CapExceeded if `target_supply` exceeds the current cap
+        /// throw InsufficientBalance if the fee recipient holds less than the burn
+        /// when success, emit `Transfer` and `SupplyAdjusted` events, unless the
+        /// total supply already is `target_supply`
+        #[ink(message)]
+        pub fn oracle_adjust_supply(&mut self, target_supply: Balance) -> Result<()> {
             let caller = self.env().caller();
-            let allowance = self.allowance(from, caller);
+            if *self.oracle != Some(caller) {
+                return Err(Error::NotOracle);
+            }
+            self.ensure_admin_op_allowed(caller)?;
 
-            if allowance < value {
-                return Err(Error::InsufficientApproval);
+            let total_supply = *self.total_supply;
+            let adjustment = target_supply.abs_diff(total_supply);
+            if adjustment == 0 {
+                return Ok(());
+            }
+            if adjustment > bps_of(total_supply, *self.max_adjustment_bps) {
+                return Err(Error::InvalidParameter);
             }
+            if target_supply > self.cap() {
+                return Err(Error::CapExceeded);
+            }
+            let fee_recipient = *self.fee_recipient;
+            let fee_recipient_balance = self.balance_of(fee_recipient);
+            let (balance, from, to) = if target_supply > total_supply {
+                (
+                    fee_recipient_balance + adjustment,
+                    None,
+                    Some(fee_recipient),
+                )
+            } else {
+                let balance = fee_recipient_balance
+                    .checked_sub(adjustment)
+                    .ok_or(Error::InsufficientBalance)?;
+                (balance, Some(fee_recipient), None)
+            };
 
-            self.inner_transfer(from, to, value)?;
-            self.allowances.insert((from, caller), allowance - value);
+            self.set_balance(fee_recipient, balance);
+            self.set_total_supply(target_supply);
+            self.env().emit_event(Transfer {
+                from,
+                to,
+                value: adjustment,
+                namespace: *self.topic_namespace,
+            });
+            self.env().emit_event(SupplyAdjusted {
+                from: total_supply,
+                to: target_supply,
+            });
             Ok(())
         }
 
-        /// internal function for transfer
-        /// used by both transfer() and transfer_from()
+        /// getter for the owner account
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            *self.owner
+        }
+
+        /// mint `value` new tokens to `to`, only callable by the owner
         ///
-        /// throw InsufficientBalance if not enough tokens on the `from` account
+        /// throw Paused while paused, unless admin ops are allowed during pause
+        /// throw CapExceeded if the total supply would exceed the current cap
         /// when success, emit `Transfer` event
-        pub fn inner_transfer(
-            &mut self,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
-        ) -> Result<()> {
-            let from_balance = self.balance_of(from);
-            if from_balance < value {
-                return Err(Error::InsufficientBalance);
-            }
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_admin_op_allowed(self.env().caller())?;
+            self.inner_mint(to, value)
+        }
 
-            self.balances.insert(from, from_balance - value);
+        /// internal function for minting `value` new tokens to `to`
+        /// used by both mint() and the execution of mint proposals
+        fn inner_mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let total_supply = self
+                .total_supply
+                .checked_add(value)
+                .filter(|total_supply| *total_supply <= self.cap())
+                .ok_or(Error::CapExceeded)?;
+            self.set_total_supply(total_supply);
             let to_balance = self.balance_of(to);
-            self.balances.insert(to, to_balance + value);
+            self.set_balance(to, to_balance + value);
             self.env().emit_event(Transfer {
-                from: Some(from),
+                from: None,
                 to: Some(to),
                 value,
+                namespace: *self.topic_namespace,
             });
-
             Ok(())
         }
-    }
 
-    /// unit tests
+        /// mint the tokens of every `(to, value)` of `allocations`,
+        /// only callable by the owner
+        /// nothing is minted unless the whole batch fits
+        ///
+        /// throw Paused while paused, unless admin ops are allowed during pause
+        /// throw Overflow if the batch plus the total supply exceeds the max balance
+        /// throw CapExceeded if the total supply would exceed the current cap
+        /// when success, emit a `Transfer` event per allocation
+        #[ink(message)]
+        pub fn mint_batch(&mut self, allocations: Vec<(AccountId, Balance)>) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_admin_op_allowed(self.env().caller())?;
+
+            let total_supply = allocations
+                .iter()
+                .try_fold(*self.total_supply, |total, (_, value)| {
+                    total.checked_add(*value)
+                })
+                .ok_or(Error::Overflow)?;
+            if total_supply > self.cap() {
+                return Err(Error::CapExceeded);
+            }
+            self.set_total_supply(total_supply);
+            for (to, value) in allocations {
+                let to_balance = self.balance_of(to);
+                self.set_balance(to, to_balance + value);
+                self.env().emit_event(Transfer {
+                    from: None,
+                    to: Some(to),
+                    value,
+                    namespace: *self.topic_namespace,
+                });
+            }
+            Ok(())
+        }
+
+        /// getter for the number of minter approvals executing a mint proposal
+        #[ink(message)]
+        pub fn mint_threshold(&self) -> u32 {
+            *self.mint_threshold
+        }
+
+        /// set the number of minter approvals executing a mint proposal,
+        /// only callable by the owner
+        /// pending proposals are executed by their next approval if they
+        /// reach the new threshold
+        ///
+        /// throw InvalidParameter if `threshold` is zero
+        #[ink(message)]
+        pub fn set_mint_threshold(&mut self, threshold: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if threshold == 0 {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.mint_threshold, threshold);
+            Ok(())
+        }
+
+        /// get the mint proposal `id`
+        /// return None if it doesn't exist or was executed
+        #[ink(message)]
+        pub fn mint_proposal(&self, id: u64) -> Option<MintProposal> {
+            self.mint_proposals.get(&id).copied()
+        }
+
+        /// check whether `minter` approved the pending mint proposal `id`
+        #[ink(message)]
+        pub fn has_approved_mint(&self, id: u64, minter: AccountId) -> bool {
+            self.mint_approvals
+                .get(&id)
+                .is_some_and(|minters| minters.contains(&minter))
+        }
+
+        /// propose to mint `amount` new tokens to `to`, counting as the
+        /// caller's approval, the mint is executed right away if that
+        /// reaches the threshold
+        /// return the id of the proposal
+        ///
+        /// throw MissingRole if the caller hasn't the minter role
+        /// throw Paused while paused
+        /// throw CapExceeded if executed and the total supply would exceed the cap
+        /// when success, emit `MintProposed` event, plus `Transfer` and
+        /// `MintExecuted` events if executed
+        #[ink(message)]
+        pub fn propose_mint(&mut self, to: AccountId, amount: Balance) -> Result<u64> {
+            let proposer = self.env().caller();
+            if !self.has_role(MINTER_ROLE, proposer) {
+                return Err(Error::MissingRole);
+            }
+            self.ensure_admin_op_allowed(proposer)?;
+
+            let id = *self.next_mint_proposal_id;
+            let proposal = MintProposal {
+                to,
+                amount,
+                approvals: 1,
+            };
+            if proposal.approvals >= *self.mint_threshold {
+                self.inner_mint(to, amount)?;
+            } else {
+                self.mint_proposals.insert(id, proposal);
+                self.mint_approvals.insert(id, vec![proposer]);
+            }
+            Lazy::set(&mut self.next_mint_proposal_id, id + 1);
+            self.env().emit_event(MintProposed {
+                id,
+                proposer,
+                to,
+                amount,
+            });
+            if proposal.approvals >= *self.mint_threshold {
+                self.env().emit_event(MintExecuted { id });
+            }
+            Ok(id)
+        }
+
+        /// approve the mint proposal `id`, the mint is executed once the
+        /// approvals reach the threshold
+        ///
+        /// throw MissingRole if the caller hasn't the minter role
+        /// throw Paused while paused
+        /// throw MintProposalNotFound if `id` doesn't exist or was executed
+        /// throw AlreadyApproved if the caller approved it already
+        /// throw CapExceeded if executed and the total supply would exceed the cap
+        /// when success, emit `MintApproved` event, plus `Transfer` and
+        /// `MintExecuted` events if executed
+        #[ink(message)]
+        pub fn approve_mint(&mut self, id: u64) -> Result<()> {
+            let minter = self.env().caller();
+            if !self.has_role(MINTER_ROLE, minter) {
+                return Err(Error::MissingRole);
+            }
+            self.ensure_admin_op_allowed(minter)?;
+            let mut proposal = self.mint_proposal(id).ok_or(Error::MintProposalNotFound)?;
+            if self.has_approved_mint(id, minter) {
+                return Err(Error::AlreadyApproved);
+            }
+
+            proposal.approvals += 1;
+            let executed = proposal.approvals >= *self.mint_threshold;
+            if executed {
+                self.inner_mint(proposal.to, proposal.amount)?;
+                // an executed proposal needs its approvals no more
+                self.mint_proposals.take(&id);
+                self.mint_approvals.take(&id);
+            } else {
+                self.mint_proposals.insert(id, proposal);
+                if let Some(minters) = self.mint_approvals.get_mut(&id) {
+                    minters.push(minter);
+                }
+            }
+            self.env().emit_event(MintApproved { id, minter });
+            if executed {
+                self.env().emit_event(MintExecuted { id });
+            }
+            Ok(())
+        }
+
+        /// burn `value` tokens from caller's account
+        ///
+        /// throw Paused while paused, unless burns are allowed during pause
+        /// or the caller is the owner and admin ops are allowed during pause
+        /// throw InsufficientBalance if not enough tokens on the caller's account
+        /// when success, emit `Transfer` event
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.inner_burn(from, value)
+        }
+
+        /// same as burn() and lower the cap by `value` too,
+        /// so the burned tokens can never be minted again
+        #[ink(message)]
+        pub fn burn_and_lower_cap(&mut self, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.inner_burn(from, value)?;
+            let cap = self.cap.saturating_sub(value);
+            Lazy::set(&mut self.cap, cap);
+            Ok(())
+        }
+
+        /// burn `value` tokens from `from`'s account out of caller's allowance,
+        /// an allowance of `Balance::MAX` is unlimited and never consumed
+        ///
+        /// throw InsufficientApproval if the caller is allowed less than `value`
+        /// throw PeriodLimitExceeded if the caller's spending limit is reached
+        /// otherwise fail as burn() does
+        /// when success, emit `Transfer` event
+        #[ink(message)]
+        pub fn burn_from(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            let spender = self.env().caller();
+            let allowance = self.allowance(from, spender);
+            if allowance < value {
+                return Err(Error::InsufficientApproval);
+            }
+            let limit = self.spend_within_limit(from, spender, value)?;
+
+            self.inner_burn(from, value)?;
+            if allowance != Balance::MAX {
+                self.set_allowance(from, spender, allowance - value);
+            }
+            if let Some(limit) = limit {
+                self.spending_limits.insert((from, spender), limit);
+            }
+            Ok(())
+        }
+
+        /// internal function for burn
+        /// used by burn(), burn_and_lower_cap() and burn_from()
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        fn inner_burn(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                if !*erc20.allow_burn_during_pause {
+                    erc20.ensure_admin_op_allowed(from)?;
+                }
+                let from_balance = erc20.balance_of(from);
+                if from_balance < value {
+                    return Err(Error::InsufficientBalance);
+                }
+
+                erc20.set_balance(from, from_balance - value);
+                let total_supply = *erc20.total_supply - value;
+                erc20.set_total_supply(total_supply);
+                erc20.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: None,
+                    value,
+                    namespace: *erc20.topic_namespace,
+                });
+                erc20.track_buyback(value);
+                Ok(())
+            })
+        }
+
+        /// getter for the v2 token amount minted per converted token
+        #[ink(message)]
+        pub fn conversion_ratio(&self) -> Balance {
+            *self.conversion_ratio
+        }
+
+        /// set the v2 token amount minted per converted token, 0 disables
+        /// convert(), only callable by the owner
+        #[ink(message)]
+        pub fn set_conversion_ratio(&mut self, ratio: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.conversion_ratio, ratio);
+            Ok(())
+        }
+
+        /// getter for the total supply of the v2 token
+        #[ink(message)]
+        pub fn v2_total_supply(&self) -> Balance {
+            *self.v2_total_supply
+        }
+
+        /// get the v2 token balance of `who`
+        /// return 0 if the account has none
+        #[ink(message)]
+        pub fn v2_balance_of(&self, who: AccountId) -> Balance {
+            self.v2_balances.get(&who).copied().unwrap_or(0)
+        }
+
+        /// burn `amount` tokens from caller's account and mint it `amount`
+        /// times the conversion ratio of the v2 token
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw ConversionDisabled if the conversion ratio is 0
+        /// throw Paused while paused, unless burns are allowed during pause
+        /// throw InsufficientBalance if not enough tokens on the caller's account
+        /// throw Overflow if the v2 total supply would overflow
+        /// when success, emit `Transfer` event to None and `Converted` event
+        #[ink(message)]
+        pub fn convert(&mut self, amount: Balance) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let ratio = *erc20.conversion_ratio;
+                if ratio == 0 {
+                    return Err(Error::ConversionDisabled);
+                }
+                let who = erc20.env().caller();
+                if !*erc20.allow_burn_during_pause {
+                    erc20.ensure_admin_op_allowed(who)?;
+                }
+                let balance = erc20.balance_of(who);
+                if balance < amount {
+                    return Err(Error::InsufficientBalance);
+                }
+                let minted = amount.checked_mul(ratio).ok_or(Error::Overflow)?;
+                let v2_total_supply = erc20
+                    .v2_total_supply
+                    .checked_add(minted)
+                    .ok_or(Error::Overflow)?;
+
+                erc20.set_balance(who, balance - amount);
+                let total_supply = *erc20.total_supply - amount;
+                erc20.set_total_supply(total_supply);
+                let v2_balance = erc20.v2_balance_of(who);
+                erc20.v2_balances.insert(who, v2_balance + minted);
+                Lazy::set(&mut erc20.v2_total_supply, v2_total_supply);
+                erc20.env().emit_event(Transfer {
+                    from: Some(who),
+                    to: None,
+                    value: amount,
+                    namespace: *erc20.topic_namespace,
+                });
+                erc20.env().emit_event(Converted {
+                    who,
+                    burned: amount,
+                    minted,
+                });
+                Ok(())
+            })
+        }
+
+        /// getter for the buyback contract and the burned amount triggering it
+        #[ink(message)]
+        pub fn buyback(&self) -> (Option<AccountId>, Balance) {
+            (*self.buyback_contract, *self.buyback_threshold)
+        }
+
+        /// getter for the token amount burned since the last buyback
+        #[ink(message)]
+        pub fn burned_since_buyback(&self) -> Balance {
+            *self.burned_since_buyback
+        }
+
+        /// call `contract`'s `trigger()` whenever `threshold` tokens were burned
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_buyback(
+            &mut self,
+            contract: Option<AccountId>,
+            threshold: Balance,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.buyback_contract, contract);
+            Lazy::set(&mut self.buyback_threshold, threshold);
+            Ok(())
+        }
+
+        /// getter for the DEX router and the treasury balance triggering a fee swap
+        #[ink(message)]
+        pub fn fee_swap(&self) -> (Option<AccountId>, Balance) {
+            (*self.router, *self.swap_threshold)
+        }
+
+        /// call `router`'s `swap_tokens_for_native(amount)` with the treasury's
+        /// balance whenever a transfer leaves it at `threshold` or more
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_fee_swap(
+            &mut self,
+            router: Option<AccountId>,
+            threshold: Balance,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.router, router);
+            Lazy::set(&mut self.swap_threshold, threshold);
+            Ok(())
+        }
+
+        /// check whether `who` is a registered AMM pair
+        #[ink(message)]
+        pub fn is_amm_pair(&self, who: AccountId) -> bool {
+            self.amm_pairs.contains_key(&who)
+        }
+
+        /// register or unregister `pair` as an AMM pair, only callable by the owner
+        #[ink(message)]
+        pub fn set_amm_pair(&mut self, pair: AccountId, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if enabled {
+                self.amm_pairs.insert(pair, ());
+            } else {
+                self.amm_pairs.take(&pair);
+            }
+            Ok(())
+        }
+
+        /// check whether the tokens sent to `who` are burned
+        #[ink(message)]
+        pub fn is_burn_address(&self, who: AccountId) -> bool {
+            self.burn_addresses.contains_key(&who)
+        }
+
+        /// register or unregister `who` as a burn address, only callable by the owner
+        /// the tokens sent to a burn address are burned instead of credited
+        #[ink(message)]
+        pub fn set_burn_address(&mut self, who: AccountId, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if enabled {
+                self.burn_addresses.insert(who, ());
+            } else {
+                self.burn_addresses.take(&who);
+            }
+            Ok(())
+        }
+
+        /// swap the treasury's balance to native currency once it reaches
+        /// the threshold, unless a swap is running or `from` is an AMM pair
+        /// a failing swap doesn't fail the transfer, the next one tries again
+        fn swap_fees(&mut self, from: AccountId) {
+            let threshold = *self.swap_threshold;
+            let router = match *self.router {
+                Some(router) if threshold != 0 && !*self.in_swap => router,
+                _ => return,
+            };
+            let amount = self.balance_of(*self.fee_recipient);
+            if amount < threshold || self.is_amm_pair(from) {
+                return;
+            }
+
+            Lazy::set(&mut self.in_swap, true);
+            let _ = self.call_reentrant(router, || {
+                build_call::<ink_env::DefaultEnvironment>()
+                    .callee(router)
+                    .gas_limit(0)
+                    .transferred_value(0)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(SWAP_TOKENS_FOR_NATIVE_SELECTOR))
+                            .push_arg(amount),
+                    )
+                    .returns::<()>()
+                    .fire()
+            });
+            Lazy::set(&mut self.in_swap, false);
+        }
+
+        /// count `burned` tokens towards the next buyback and trigger it
+        /// once the threshold is crossed
+        ///
+        /// a failing `trigger()` doesn't fail the burn, it emits `BuybackFailed`
+        /// and keeps the counter so the next burn tries again
+        fn track_buyback(&mut self, burned: Balance) {
+            let burned = self.burned_since_buyback.saturating_add(burned);
+            Lazy::set(&mut self.burned_since_buyback, burned);
+
+            let threshold = *self.buyback_threshold;
+            let contract = match *self.buyback_contract {
+                Some(contract) if threshold != 0 && burned >= threshold => contract,
+                _ => return,
+            };
+            let result = build_call::<ink_env::DefaultEnvironment>()
+                .callee(contract)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(ExecutionInput::new(Selector::new(TRIGGER_SELECTOR)))
+                .returns::<()>()
+                .fire();
+            match result {
+                Ok(()) => {
+                    Lazy::set(&mut self.burned_since_buyback, 0);
+                    self.env().emit_event(BuybackTriggered { contract, burned });
+                }
+                Err(_) => self.env().emit_event(BuybackFailed { contract, burned }),
+            }
+        }
+
+        /// get the owned token amount for certain `who`, including its share
+        /// of the reflections unless it is excluded from them
+        /// return 0 if account doesn't exist.
+        #[ink(message)]
+        pub fn balance_of(&self, who: AccountId) -> Balance {
+            let stored = self.balances.get(&who).copied().unwrap_or(0);
+            if self.is_reflection_excluded(who) {
+                return stored;
+            }
+            stored / self.reflection_rate()
+        }
+
+        /// reflected balance a token is worth, falling with every reflection
+        /// while the reflected supply is empty, `Balance::MAX` is split over
+        /// the cap as RFI splits it over the total supply, so the reflected
+        /// supply can't overflow before the cap is reached
+        fn reflection_rate(&self) -> Balance {
+            match (*self.reflected_supply, *self.reflected_tokens) {
+                (0, _) | (_, 0) => Balance::MAX / self.cap().max(1),
+                (supply, tokens) => supply / tokens,
+            }
+        }
+
+        /// check whether `who` is excluded from reflections
+        #[ink(message)]
+        pub fn is_reflection_excluded(&self, who: AccountId) -> bool {
+            self.reflection_excluded.contains_key(&who)
+        }
+
+        /// exclude `who` from reflections or let it take part in them again,
+        /// only callable by the owner
+        /// an excluded account, e.g. an AMM pair or the treasury, keeps its
+        /// balance as is and gets no share of the reflections
+        #[ink(message)]
+        pub fn set_reflection_excluded(&mut self, who: AccountId, excluded: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if excluded == self.is_reflection_excluded(who) {
+                return Ok(());
+            }
+            // move the balance out of the reflected supply and back
+            let balance = self.balance_of(who);
+            self.set_balance(who, 0);
+            if excluded {
+                self.reflection_excluded.insert(who, ());
+            } else {
+                self.reflection_excluded.take(&who);
+            }
+            self.set_balance(who, balance);
+            Ok(())
+        }
+
+        /// getter for the number of decimals of the token
+        #[ink(message)]
+        pub fn decimals(&self) -> u8 {
+            *self.decimals
+        }
+
+        /// getter for the number of fractional digits balance_of_formatted() shows
+        #[ink(message)]
+        pub fn display_decimals(&self) -> u8 {
+            self.display_decimals.unwrap_or(*self.decimals)
+        }
+
+        /// show `display_decimals` fractional digits in balance_of_formatted(),
+        /// all the decimals if None, only callable by the owner
+        ///
+        /// throw InvalidParameter if `display_decimals` is above decimals()
+        #[ink(message)]
+        pub fn set_display_decimals(&mut self, display_decimals: Option<u8>) -> Result<()> {
+            self.ensure_owner()?;
+            if display_decimals.is_some_and(|digits| digits > *self.decimals) {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.display_decimals, display_decimals);
+            Ok(())
+        }
+
+        /// get the owned token amount for certain `who` in whole tokens,
+        /// with the fractional part truncated to display_decimals() digits
+        #[ink(message)]
+        pub fn balance_of_formatted(&self, who: AccountId) -> String {
+            format_amount(
+                self.balance_of(who),
+                *self.decimals,
+                self.display_decimals(),
+            )
+        }
+
+        /// get `who`'s share of the total supply in basis points, rounded down
+        /// return 0 if there is no supply
+        #[ink(message)]
+        pub fn balance_share_bps(&self, who: AccountId) -> u16 {
+            let total_supply = *self.total_supply;
+            if total_supply == 0 {
+                return 0;
+            }
+            mul_div(self.balance_of(who), MAX_BPS, total_supply).min(MAX_BPS) as u16
+        }
+
+        /// get the optional modules of the token, always available ones
+        /// and configurable ones as far as they are enabled
+        /// signed permits aren't supported so never listed
+        #[ink(message)]
+        pub fn supported_features(&self) -> Vec<FeatureFlag> {
+            let mut features = Vec::new();
+            if *self.fee_bps != 0
+                || *self.burn_bps != 0
+                || *self.min_fee != 0
+                || *self.reflection_bps != 0
+            {
+                features.push(FeatureFlag::Fees);
+            }
+            features.extend([
+                FeatureFlag::Pausable,
+                FeatureFlag::Staking,
+                FeatureFlag::Vesting,
+            ]);
+            if *self.whitelist_mode {
+                features.push(FeatureFlag::Whitelist);
+            }
+            if *self.cooldown_blocks != 0 {
+                features.push(FeatureFlag::Cooldown);
+            }
+            if self.router.is_some() && *self.swap_threshold != 0 {
+                features.push(FeatureFlag::FeeSwap);
+            }
+            features
+        }
+
+        /// getter for the number of entries of the main storage maps
+        #[ink(message)]
+        pub fn storage_stats(&self) -> StorageStats {
+            *self.storage_stats
+        }
+
+        /// store the balance of `who`, a zero balance is removed rather than stored
+        /// unless `who` is excluded from reflections, it is stored as a reflected
+        /// balance at the current rate of the reflected supply
+        fn set_balance(&mut self, who: AccountId, value: Balance) {
+            let value = if self.is_reflection_excluded(who) {
+                value
+            } else {
+                let (supply, tokens) = (*self.reflected_supply, *self.reflected_tokens);
+                let old = self.balances.get(&who).copied().unwrap_or(0);
+                let old_tokens = self.balance_of(who);
+                let reflected = value * self.reflection_rate();
+                Lazy::set(&mut self.reflected_supply, supply - old + reflected);
+                // the rounded down rate may value a balance a token above them
+                Lazy::set(
+                    &mut self.reflected_tokens,
+                    tokens.saturating_sub(old_tokens) + value,
+                );
+                reflected
+            };
+            let existed = if value == 0 {
+                self.balances.take(&who).is_some()
+            } else {
+                self.balances.insert(who, value).is_some()
+            };
+            let stats = Lazy::get_mut(&mut self.storage_stats);
+            stats.holder_count = count_entry(stats.holder_count, existed, value != 0);
+        }
+
+        /// get the token amount which `spender` is allowed to withdraw
+        /// from `owner`'s account, including what regenerated since the last spend
+        /// and at most what is left of a decaying approval
+        /// return 0 if no allowance was set
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            let allowance = self.allowances.get(&(owner, spender)).copied().unwrap_or(0);
+            if let Some(decay) = self.decays.get(&(owner, spender)) {
+                let elapsed = self.env().block_number() - decay.start;
+                if elapsed >= decay.decay_blocks {
+                    return 0;
+                }
+                let (decay_blocks, remaining) = (
+                    Balance::from(decay.decay_blocks),
+                    Balance::from(decay.decay_blocks - elapsed),
+                );
+                let left = decay.value / decay_blocks * remaining
+                    + decay.value % decay_blocks * remaining / decay_blocks;
+                return allowance.min(left);
+            }
+            match self.regenerations.get(&(owner, spender)) {
+                Some(regeneration) => {
+                    let elapsed =
+                        Balance::from(self.env().block_number() - regeneration.last_update);
+                    allowance
+                        .saturating_add(elapsed.saturating_mul(regeneration.regen_per_block))
+                        .min(regeneration.max)
+                }
+                None => allowance,
+            }
+        }
+
+        /// getter for the flag requiring spenders to accept approvals
+        #[ink(message)]
+        pub fn require_spender_acceptance(&self) -> bool {
+            *self.require_spender_acceptance
+        }
+
+        /// when enabled, spenders can only spend approved tokens of owners
+        /// they accepted, only callable by the owner
+        #[ink(message)]
+        pub fn set_require_spender_acceptance(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.require_spender_acceptance, enabled);
+            Ok(())
+        }
+
+        /// check whether `spender` accepted to spend `owner`'s tokens
+        #[ink(message)]
+        pub fn is_spender_accepted(&self, owner: AccountId, spender: AccountId) -> bool {
+            self.spender_accepted.contains_key(&(owner, spender))
+        }
+
+        /// accept to spend the tokens `owner` approves the caller for
+        #[ink(message)]
+        pub fn accept_spender_role(&mut self, owner: AccountId) {
+            let spender = self.env().caller();
+            self.spender_accepted.insert((owner, spender), ());
+        }
+
+        /// stop accepting to spend `owner`'s tokens
+        #[ink(message)]
+        pub fn renounce_spender_role(&mut self, owner: AccountId) {
+            let spender = self.env().caller();
+            self.spender_accepted.take(&(owner, spender));
+        }
+
+        /// get the allowance of each of `spenders` over `owner`'s tokens, in order
+        #[ink(message)]
+        pub fn allowances_for(&self, owner: AccountId, spenders: Vec<AccountId>) -> Vec<Balance> {
+            spenders
+                .into_iter()
+                .map(|spender| self.allowance(owner, spender))
+                .collect()
+        }
+
+        /// get how the allowance of `spender` over `owner`'s tokens refills
+        /// return None if it doesn't regenerate
+        #[ink(message)]
+        pub fn allowance_regeneration(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> Option<AllowanceRegeneration> {
+            self.regenerations.get(&(owner, spender)).copied()
+        }
+
+        /// getter for the flag clearing the allowances of emptied accounts
+        #[ink(message)]
+        pub fn clear_allowances_on_empty(&self) -> bool {
+            *self.clear_allowances_on_empty
+        }
+
+        /// when enabled, an account whose balance drops to zero by a transfer
+        /// loses all the allowances it granted, only callable by the owner
+        #[ink(message)]
+        pub fn set_clear_allowances_on_empty(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.clear_allowances_on_empty, enabled);
+            Ok(())
+        }
+
+        /// transfer `value` amount of tokens from caller's account to `to`
+        /// call internal transfer func
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller(); // same as Self::env().caller();
+            self.inner_transfer(from, to, value)
+        }
+
+        /// same as transfer() but make sure `to` receives at least `min_received`
+        /// tokens once the fee and burn are taken
+        ///
+        /// throw SlippageExceeded if the net amount is below `min_received`
+        #[ink(message)]
+        pub fn transfer_min_received(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            min_received: Balance,
+        ) -> Result<()> {
+            let from = self.env().caller();
+            let preview = self.preview_transfer(from, to, value);
+            if preview.net_to_recipient < min_received {
+                return Err(Error::SlippageExceeded);
+            }
+            self.inner_transfer(from, to, value)
+        }
+
+        /// same as transfer() but done at most once per caller's `key`,
+        /// so a retried payment isn't sent twice
+        ///
+        /// throw DuplicateRequest if caller already transferred with `key`
+        #[ink(message)]
+        pub fn transfer_idempotent(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            key: [u8; 32],
+        ) -> Result<()> {
+            let from = self.env().caller();
+            if self.processed_keys.contains_key(&(from, key)) {
+                return Err(Error::DuplicateRequest);
+            }
+            self.inner_transfer(from, to, value)?;
+            self.processed_keys.insert((from, key), ());
+            Ok(())
+        }
+
+        /// same as transfer() but tag the payment with an invoice id,
+        /// incrementing contract-wide, for bookkeeping
+        /// return the invoice id
+        ///
+        /// when success, emit `Invoice` event besides the transfer's events
+        #[ink(message)]
+        pub fn transfer_invoice(&mut self, to: AccountId, value: Balance) -> Result<u64> {
+            let from = self.env().caller();
+            self.inner_transfer(from, to, value)?;
+            let id = *self.next_invoice_id;
+            Lazy::set(&mut self.next_invoice_id, id + 1);
+            self.env().emit_event(Invoice {
+                id,
+                from,
+                to,
+                value,
+                block: self.env().block_number(),
+            });
+            Ok(id)
+        }
+
+        /// getter for the vault contract
+        #[ink(message)]
+        pub fn vault(&self) -> Option<AccountId> {
+            *self.vault
+        }
+
+        /// set the vault contract redeeming the shortfall of `transfer_with_vault()`,
+        /// None removes it, only callable by the owner
+        #[ink(message)]
+        pub fn set_vault(&mut self, vault: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.vault, vault);
+            Ok(())
+        }
+
+        /// same as transfer() but when caller's balance is short of `value`,
+        /// first call the vault's `redeem(caller, shortfall)` which must
+        /// credit the shortfall back to the caller
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw VaultRedeemFailed if no vault is set, the call fails
+        /// or the caller is still short afterwards
+        #[ink(message)]
+        pub fn transfer_with_vault(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let from = erc20.env().caller();
+                let balance = erc20.balance_of(from);
+                if balance < value {
+                    let vault = erc20.vault().ok_or(Error::VaultRedeemFailed)?;
+                    build_call::<ink_env::DefaultEnvironment>()
+                        .callee(vault)
+                        .gas_limit(0)
+                        .transferred_value(0)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(REDEEM_SELECTOR))
+                                .push_arg(from)
+                                .push_arg(value - balance),
+                        )
+                        .returns::<()>()
+                        .fire()
+                        .map_err(|_| Error::VaultRedeemFailed)?;
+                    if erc20.balance_of(from) < value {
+                        return Err(Error::VaultRedeemFailed);
+                    }
+                }
+                erc20.unguarded_transfer(from, to, value)
+            })
+        }
+
+        /// getter for the backing token
+        #[ink(message)]
+        pub fn backing_token(&self) -> Option<AccountId> {
+            *self.backing_token
+        }
+
+        /// set the token backing this one, only callable by the owner
+        #[ink(message)]
+        pub fn set_backing_token(&mut self, backing_token: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.backing_token, backing_token);
+            Ok(())
+        }
+
+        /// getter for the wrapped token and its number of decimals
+        #[ink(message)]
+        pub fn wrapped_token(&self) -> (Option<AccountId>, u8) {
+            (*self.wrapped_token, *self.underlying_decimals)
+        }
+
+        /// set the token wrapped by wrap() and unwrap() with its number of
+        /// decimals, None disables wrapping, only callable by the owner
+        ///
+        /// throw InvalidParameter if `underlying_decimals` is above 38
+        #[ink(message)]
+        pub fn set_wrapped_token(
+            &mut self,
+            wrapped_token: Option<AccountId>,
+            underlying_decimals: u8,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if underlying_decimals > MAX_DECIMALS {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.wrapped_token, wrapped_token);
+            Lazy::set(&mut self.underlying_decimals, underlying_decimals);
+            Ok(())
+        }
+
+        /// pull `amount` of the wrapped token from caller's account through
+        /// its `transfer_from(from, to, value)` message and mint the same
+        /// amount of tokens to the caller, scaled to this token's decimals
+        /// the dust of `amount` not worth a base unit is left to the caller
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw WrapFailed if no wrapped token is set or its transfer fails
+        /// throw Paused while paused
+        /// throw InvalidParameter if `amount` is worth no base unit
+        /// throw CapExceeded if the total supply would exceed the current cap
+        /// when success, emit `Transfer` event
+        #[ink(message)]
+        pub fn wrap(&mut self, amount: Balance) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let wrapped_token = erc20.wrapped_token.ok_or(Error::WrapFailed)?;
+                let from = erc20.env().caller();
+                erc20.ensure_admin_op_allowed(from)?;
+                let (decimals, underlying_decimals) = (*erc20.decimals, *erc20.underlying_decimals);
+                let minted =
+                    scale_decimals(amount, underlying_decimals, decimals).ok_or(Error::Overflow)?;
+                if minted == 0 {
+                    return Err(Error::InvalidParameter);
+                }
+                let pulled =
+                    scale_decimals(minted, decimals, underlying_decimals).ok_or(Error::Overflow)?;
+                if erc20
+                    .total_supply
+                    .checked_add(minted)
+                    .filter(|total_supply| *total_supply <= erc20.cap())
+                    .is_none()
+                {
+                    return Err(Error::CapExceeded);
+                }
+
+                let result = build_call::<ink_env::DefaultEnvironment>()
+                    .callee(wrapped_token)
+                    .gas_limit(0)
+                    .transferred_value(0)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(TRANSFER_FROM_SELECTOR))
+                            .push_arg(from)
+                            .push_arg(erc20.env().account_id())
+                            .push_arg(pulled),
+                    )
+                    .returns::<ReturnType<Result<()>>>()
+                    .fire();
+                if !matches!(result, Ok(Ok(()))) {
+                    return Err(Error::WrapFailed);
+                }
+                erc20.inner_mint(from, minted)
+            })
+        }
+
+        /// burn `value` tokens from caller's account and send it the same
+        /// amount of the wrapped token, scaled to its decimals, through its
+        /// `transfer(to, value)` message
+        /// the dust of `value` not worth a base unit of the wrapped token
+        /// isn't burned
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw WrapFailed if no wrapped token is set or its transfer fails,
+        /// nothing is burned then
+        /// throw Paused while paused
+        /// throw InvalidParameter if `value` is worth no base unit of the wrapped token
+        /// throw InsufficientBalance if not enough tokens on the caller's account
+        /// when success, emit `Transfer` event to None
+        #[ink(message)]
+        pub fn unwrap(&mut self, value: Balance) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let wrapped_token = erc20.wrapped_token.ok_or(Error::WrapFailed)?;
+                let from = erc20.env().caller();
+                erc20.ensure_admin_op_allowed(from)?;
+                let (decimals, underlying_decimals) = (*erc20.decimals, *erc20.underlying_decimals);
+                let released =
+                    scale_decimals(value, decimals, underlying_decimals).ok_or(Error::Overflow)?;
+                if released == 0 {
+                    return Err(Error::InvalidParameter);
+                }
+                let burned = scale_decimals(released, underlying_decimals, decimals)
+                    .ok_or(Error::Overflow)?;
+                let from_balance = erc20.balance_of(from);
+                if from_balance < burned {
+                    return Err(Error::InsufficientBalance);
+                }
+
+                // debit first so the wrapped token can't unwrap the same tokens again
+                erc20.set_balance(from, from_balance - burned);
+                let result = build_call::<ink_env::DefaultEnvironment>()
+                    .callee(wrapped_token)
+                    .gas_limit(0)
+                    .transferred_value(0)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(TRANSFER_SELECTOR))
+                            .push_arg(from)
+                            .push_arg(released),
+                    )
+                    .returns::<ReturnType<Result<()>>>()
+                    .fire();
+                if !matches!(result, Ok(Ok(()))) {
+                    let from_balance = erc20.balance_of(from);
+                    erc20.set_balance(from, from_balance + burned);
+                    return Err(Error::WrapFailed);
+                }
+
+                let total_supply = *erc20.total_supply - burned;
+                erc20.set_total_supply(total_supply);
+                erc20.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: None,
+                    value: burned,
+                    namespace: *erc20.topic_namespace,
+                });
+                Ok(())
+            })
+        }
+
+        /// burn `value` tokens from caller's account and send it the same share
+        /// of the contract's backing token holdings through the backing
+        /// token's `transfer(to, value)` message
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw RedeemFailed if no backing token is set, during another
+        /// redemption or if the backing token fails, nothing is burned then
+        /// throw InsufficientBalance if not enough tokens on the caller's account
+        /// when success, emit `Transfer` event to None
+        #[ink(message)]
+        pub fn redeem(&mut self, value: Balance) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let backing_token = erc20.backing_token().ok_or(Error::RedeemFailed)?;
+                if *erc20.in_redeem {
+                    return Err(Error::RedeemFailed);
+                }
+                let from = erc20.env().caller();
+                if !*erc20.allow_burn_during_pause {
+                    erc20.ensure_admin_op_allowed(from)?;
+                }
+                let from_balance = erc20.balance_of(from);
+                if from_balance < value {
+                    return Err(Error::InsufficientBalance);
+                }
+
+                let backing_balance = build_call::<ink_env::DefaultEnvironment>()
+                    .callee(backing_token)
+                    .gas_limit(0)
+                    .transferred_value(0)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(BALANCE_OF_SELECTOR))
+                            .push_arg(erc20.env().account_id()),
+                    )
+                    .returns::<ReturnType<Balance>>()
+                    .fire()
+                    .map_err(|_| Error::RedeemFailed)?;
+                let total_supply = *erc20.total_supply;
+                let redeemed =
+                    redeemed_share(backing_balance, value, total_supply).ok_or(Error::Overflow)?;
+
+                // debit first so the backing token can't redeem the same tokens again
+                Lazy::set(&mut erc20.in_redeem, true);
+                erc20.set_balance(from, from_balance - value);
+                let result = build_call::<ink_env::DefaultEnvironment>()
+                    .callee(backing_token)
+                    .gas_limit(0)
+                    .transferred_value(0)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(TRANSFER_SELECTOR))
+                            .push_arg(from)
+                            .push_arg(redeemed),
+                    )
+                    .returns::<ReturnType<Result<()>>>()
+                    .fire();
+                Lazy::set(&mut erc20.in_redeem, false);
+                if !matches!(result, Ok(Ok(()))) {
+                    let from_balance = erc20.balance_of(from);
+                    erc20.set_balance(from, from_balance + value);
+                    return Err(Error::RedeemFailed);
+                }
+
+                erc20.set_total_supply(total_supply - value);
+                erc20.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: None,
+                    value,
+                    namespace: *erc20.topic_namespace,
+                });
+                Ok(())
+            })
+        }
+
+        /// approve `to` to withdraw from caller's account for `value` token amount
+        /// `Balance::MAX` approves an unlimited amount
+        /// emit `Approval` event
+        #[ink(message)]
+        pub fn approve(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self.inner_approve(owner, to, value)
+        }
+
+        /// same as approve() and let `spender` spend at most `per_period`
+        /// tokens every `period_blocks` blocks
+        /// a later approve() of `spender` removes the limit
+        ///
+        /// throw InvalidParameter if `period_blocks` is 0
+        #[ink(message)]
+        pub fn approve_with_limit(
+            &mut self,
+            spender: AccountId,
+            total: Balance,
+            per_period: Balance,
+            period_blocks: BlockNumber,
+        ) -> Result<()> {
+            if period_blocks == 0 {
+                return Err(Error::InvalidParameter);
+            }
+            let owner = self.env().caller();
+            self.inner_approve(owner, spender, total)?;
+            let limit = SpendingLimit {
+                per_period_limit: per_period,
+                period_blocks,
+                spent_this_period: 0,
+                period_start: self.env().block_number(),
+            };
+            self.spending_limits.insert((owner, spender), limit);
+            Ok(())
+        }
+
+        /// approve `spender` for `max` tokens, refilling by `regen_per_block`
+        /// every block up to `max` again once spent
+        /// a later approve() of `spender` stops the regeneration
+        #[ink(message)]
+        pub fn approve_regenerating(
+            &mut self,
+            spender: AccountId,
+            max: Balance,
+            regen_per_block: Balance,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            self.inner_approve(owner, spender, max)?;
+            let regeneration = AllowanceRegeneration {
+                max,
+                regen_per_block,
+                last_update: self.env().block_number(),
+            };
+            self.regenerations.insert((owner, spender), regeneration);
+            Ok(())
+        }
+
+        /// approve `spender` for `value` tokens, decaying linearly to zero
+        /// over `decay_blocks` blocks, spending doesn't slow the decay down
+        /// a later approve() of `spender` stops the decay
+        ///
+        /// throw InvalidParameter if `decay_blocks` is 0
+        #[ink(message)]
+        pub fn approve_decaying(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+            decay_blocks: BlockNumber,
+        ) -> Result<()> {
+            if decay_blocks == 0 {
+                return Err(Error::InvalidParameter);
+            }
+            let owner = self.env().caller();
+            self.inner_approve(owner, spender, value)?;
+            let decay = AllowanceDecay {
+                value,
+                start: self.env().block_number(),
+                decay_blocks,
+            };
+            self.decays.insert((owner, spender), decay);
+            Ok(())
+        }
+
+        /// get how the allowance of `spender` over `owner`'s tokens decays
+        /// return None if it doesn't decay
+        #[ink(message)]
+        pub fn allowance_decay(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> Option<AllowanceDecay> {
+            self.decays.get(&(owner, spender)).copied()
+        }
+
+        /// get the spending limit of `spender` over `owner`'s tokens
+        /// return None if the allowance isn't limited
+        #[ink(message)]
+        pub fn spending_limit(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> Option<SpendingLimit> {
+            self.spending_limits.get(&(owner, spender)).copied()
+        }
+
+        /// check `spender` may spend `value` more of `owner`'s tokens in
+        /// the current period, starting a new period when the last one is over
+        ///
+        /// throw PeriodLimitExceeded if the period's limit would be exceeded
+        /// return the spending limit to store once the spend is done,
+        /// None if the allowance isn't limited
+        fn spend_within_limit(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+        ) -> Result<Option<SpendingLimit>> {
+            let mut limit = match self.spending_limit(owner, spender) {
+                Some(limit) => limit,
+                None => return Ok(None),
+            };
+            let now = self.env().block_number();
+            let elapsed = now - limit.period_start;
+            if elapsed >= limit.period_blocks {
+                limit.period_start = now - elapsed % limit.period_blocks;
+                limit.spent_this_period = 0;
+            }
+            limit.spent_this_period = limit
+                .spent_this_period
+                .checked_add(value)
+                .filter(|spent| *spent <= limit.per_period_limit)
+                .ok_or(Error::PeriodLimitExceeded)?;
+            Ok(Some(limit))
+        }
+
+        /// get the (daily outbound limit, token amount sent today, block number
+        /// the day started at) of `who`, a limit of 0 means no limit
+        /// a raised limit counts once its delay is over
+        #[ink(message)]
+        pub fn daily_limit(&self, who: AccountId) -> (Balance, Balance, BlockNumber) {
+            let mut daily_limit = self.daily_limits.get(&who).copied().unwrap_or_default();
+            let now = self.env().block_number();
+            if let Some((limit, effective_block)) = self.pending_daily_limit(who) {
+                if now >= effective_block {
+                    daily_limit.0 = limit;
+                }
+            }
+            let elapsed = now - daily_limit.2;
+            if elapsed >= DAY_BLOCKS {
+                daily_limit.1 = 0;
+                daily_limit.2 = now - elapsed % DAY_BLOCKS;
+            }
+            daily_limit
+        }
+
+        /// get the raised daily limit of `who` and the block it applies from
+        /// return None if no raise is pending
+        #[ink(message)]
+        pub fn pending_daily_limit(&self, who: AccountId) -> Option<(Balance, BlockNumber)> {
+            self.pending_daily_limits.get(&who).copied()
+        }
+
+        /// limit the tokens the caller can send per day to `limit`, 0 means
+        /// no limit
+        /// a first or lower limit applies right away, a higher one or removing
+        /// the limit applies after a day so a stolen key can't lift it at once
+        #[ink(message)]
+        pub fn set_daily_limit(&mut self, limit: Balance) {
+            let who = self.env().caller();
+            let (current, spent, day_start) = self.daily_limit(who);
+            if current != 0 && (limit == 0 || limit > current) {
+                let effective_block = self.env().block_number().saturating_add(DAY_BLOCKS);
+                self.pending_daily_limits
+                    .insert(who, (limit, effective_block));
+            } else {
+                self.pending_daily_limits.take(&who);
+                self.daily_limits.insert(who, (limit, spent, day_start));
+            }
+        }
+
+        /// throw DailyLimitExceeded if `from` can't send `value` more tokens today
+        fn check_daily_limit(&self, from: AccountId, value: Balance) -> Result<()> {
+            let (limit, spent, _) = self.daily_limit(from);
+            if limit != 0 && spent.saturating_add(value) > limit {
+                return Err(Error::DailyLimitExceeded);
+            }
+            Ok(())
+        }
+
+        /// count `value` tokens as sent today by `from`, applying its raised
+        /// limit once the delay is over
+        fn record_daily_spend(&mut self, from: AccountId, value: Balance) {
+            if !self.daily_limits.contains_key(&from) {
+                return;
+            }
+            let (limit, spent, day_start) = self.daily_limit(from);
+            if self
+                .pending_daily_limit(from)
+                .is_some_and(|(pending, _)| pending == limit)
+            {
+                self.pending_daily_limits.take(&from);
+            }
+            if limit == 0 {
+                self.daily_limits.take(&from);
+            } else {
+                self.daily_limits
+                    .insert(from, (limit, spent.saturating_add(value), day_start));
+            }
+        }
+
+        /// same as approve() with a human readable `label` for `spender`
+        /// the label isn't stored, it is only emitted in a `SpenderLabeled` event
+        #[ink(message)]
+        pub fn approve_with_label(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+            label: [u8; 16],
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            self.inner_approve(owner, spender, value)?;
+            self.env().emit_event(SpenderLabeled {
+                owner,
+                spender,
+                label,
+            });
+            Ok(())
+        }
+
+        /// approve `spender` for `value` tokens then call its
+        /// `receive_approval(owner, value, data)` message in the same transaction,
+        /// which may spend the allowance right away
+        /// the previous allowance is restored with its spending limit,
+        /// regeneration or decay if the call fails
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw DeadlinePassed if the current block is after `deadline_block`
+        /// throw CallFailed if the spender call fails
+        /// emit `Approval` event before the call, and another one restoring
+        /// the previous allowance if it fails
+        #[ink(message)]
+        pub fn approve_call_before(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+            deadline_block: BlockNumber,
+        ) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                if erc20.env().block_number() > deadline_block {
+                    return Err(Error::DeadlinePassed);
+                }
+                let owner = erc20.env().caller();
+                erc20.check_approval(owner, spender, value)?;
+                let snapshot = erc20.snapshot_allowance(owner, spender);
+                erc20.write_approval(owner, spender, value);
+                let result = erc20.call_reentrant(spender, || {
+                    build_call::<ink_env::DefaultEnvironment>()
+                        .callee(spender)
+                        .gas_limit(0)
+                        .transferred_value(0)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(RECEIVE_APPROVAL_SELECTOR))
+                                .push_arg(owner)
+                                .push_arg(value)
+                                .push_arg(data),
+                        )
+                        .returns::<()>()
+                        .fire()
+                });
+                erc20.settle_approve_call(owner, spender, snapshot, result.is_ok())
+            })
+        }
+
+        /// finish approve_call_before() once the spender was called,
+        /// restoring the allowance as `snapshot` took it unless `call_succeeded`
+        ///
+        /// emit `Approval` event with the restored allowance if the call failed
+        fn settle_approve_call(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            snapshot: AllowanceSnapshot,
+            call_succeeded: bool,
+        ) -> Result<()> {
+            if call_succeeded {
+                return Ok(());
+            }
+            self.restore_allowance(owner, spender, snapshot);
+            let value = self.allowance(owner, spender);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            if value == 0 {
+                self.env().emit_event(ApprovalRevoked { owner, spender });
+            }
+            Err(Error::CallFailed)
+        }
+
+        /// take the allowance of `spender` over `owner`'s tokens with its
+        /// records, as write_approval() is about to replace them
+        fn snapshot_allowance(&self, owner: AccountId, spender: AccountId) -> AllowanceSnapshot {
+            let key = (owner, spender);
+            AllowanceSnapshot {
+                allowance: self.allowances.get(&key).copied(),
+                spending_limit: self.spending_limits.get(&key).copied(),
+                regeneration: self.regenerations.get(&key).copied(),
+                decay: self.decays.get(&key).copied(),
+                spenders: self.owner_spenders.get(&owner).cloned(),
+            }
+        }
+
+        /// write back the allowance and records `snapshot` took
+        fn restore_allowance(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            snapshot: AllowanceSnapshot,
+        ) {
+            let key = (owner, spender);
+            let existed = match snapshot.allowance {
+                Some(allowance) => self.allowances.insert(key, allowance).is_some(),
+                None => self.allowances.take(&key).is_some(),
+            };
+            let stats = Lazy::get_mut(&mut self.storage_stats);
+            stats.allowance_count =
+                count_entry(stats.allowance_count, existed, snapshot.allowance.is_some());
+            match snapshot.spending_limit {
+                Some(limit) => self.spending_limits.insert(key, limit),
+                None => self.spending_limits.take(&key),
+            };
+            match snapshot.regeneration {
+                Some(regeneration) => self.regenerations.insert(key, regeneration),
+                None => self.regenerations.take(&key),
+            };
+            match snapshot.decay {
+                Some(decay) => self.decays.insert(key, decay),
+                None => self.decays.take(&key),
+            };
+            match snapshot.spenders {
+                Some(spenders) => self.owner_spenders.insert(owner, spenders),
+                None => self.owner_spenders.take(&owner),
+            };
+        }
+
+        /// transfers `value` tokens on the behalf of `from` to the account `to`
+        ///
+        /// The caller must be allowed to do so, that is:
+        /// self.allowance(from, caller) must exist and sufficient allowance must be provided
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            self.inner_transfer_from(caller, from, to, value)?;
+            Ok(())
+        }
+
+        /// same as transfer_from() and return caller's remaining allowance,
+        /// `Balance::MAX` for an unlimited one
+        #[ink(message)]
+        pub fn transfer_from_returning(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<Balance> {
+            let caller = self.env().caller();
+            self.inner_transfer_from(caller, from, to, value)
+        }
+
+        /// get the pull payment schedule `from` granted to `service`
+        /// return None if no subscription was set
+        #[ink(message)]
+        pub fn subscription(&self, from: AccountId, service: AccountId) -> Option<Subscription> {
+            self.subscriptions.get(&(from, service)).copied()
+        }
+
+        /// allow `service` to pull `amount` tokens from caller's account every
+        /// `interval_blocks` blocks, starting one interval from now
+        /// the pulls are also bounded by the allowance granted to `service`
+        ///
+        /// throw InvalidParameter if `interval_blocks` is 0
+        #[ink(message)]
+        pub fn set_subscription(
+            &mut self,
+            service: AccountId,
+            amount: Balance,
+            interval_blocks: BlockNumber,
+        ) -> Result<()> {
+            if interval_blocks == 0 {
+                return Err(Error::InvalidParameter);
+            }
+            let from = self.env().caller();
+            self.subscriptions.insert(
+                (from, service),
+                Subscription {
+                    amount_per_interval: amount,
+                    interval_blocks,
+                    last_pull_block: self.env().block_number(),
+                },
+            );
+            Ok(())
+        }
+
+        /// pull the payments due by `from` to the caller for every interval
+        /// elapsed since the last pull, spending the caller's allowance
+        ///
+        /// throw NoSubscription if `from` didn't subscribe to the caller
+        /// throw IntervalNotElapsed if no full interval elapsed since the last pull
+        #[ink(message)]
+        pub fn pull_payment(&mut self, from: AccountId) -> Result<()> {
+            let service = self.env().caller();
+            let mut subscription = self
+                .subscription(from, service)
+                .ok_or(Error::NoSubscription)?;
+            let intervals = (self.env().block_number() - subscription.last_pull_block)
+                / subscription.interval_blocks;
+            if intervals == 0 {
+                return Err(Error::IntervalNotElapsed);
+            }
+
+            let due = subscription
+                .amount_per_interval
+                .saturating_mul(Balance::from(intervals));
+            self.inner_transfer_from(service, from, service, due)?;
+            subscription.last_pull_block += intervals * subscription.interval_blocks;
+            self.subscriptions.insert((from, service), subscription);
+            Ok(())
+        }
+
+        /// get the (sender, recipient, value) of the pending claim `id`
+        /// return None if the claim doesn't exist or was already settled
+        #[ink(message)]
+        pub fn get_claim(&self, id: u64) -> Option<(AccountId, AccountId, Balance)> {
+            self.claims.get(&id).copied()
+        }
+
+        /// lock `value` tokens from caller's account in an escrow claimable by `to`
+        /// until claimed, the caller can take them back with `reclaim`
+        /// the escrowed tokens are held by the contract account
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// fail as check_sender() does
+        /// return the id of the created claim
+        /// when success, emit `Transfer` event to the contract account
+        #[ink(message)]
+        pub fn create_claim(&mut self, to: AccountId, value: Balance) -> Result<u64> {
+            self.non_reentrant(|erc20| {
+                let from = erc20.env().caller();
+                let volume_window = erc20.check_sender(from, value)?;
+
+                let id = *erc20.next_claim_id;
+                Lazy::set(&mut erc20.next_claim_id, id + 1);
+                let from_balance = erc20.balance_of(from);
+                erc20.set_balance(from, from_balance - value);
+                erc20.record_spend(from, value, volume_window);
+                erc20.claims.insert(id, (from, to, value));
+                erc20.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: Some(erc20.env().account_id()),
+                    value,
+                    namespace: *erc20.topic_namespace,
+                });
+                Ok(id)
+            })
+        }
+
+        /// credit `value` tokens held by the contract, escrowed or locked, to `to`
+        ///
+        /// emit `Transfer` event from the contract account
+        fn release_escrow(&mut self, to: AccountId, value: Balance) {
+            let to_balance = self.balance_of(to);
+            self.set_balance(to, to_balance + value);
+            self.env().emit_event(Transfer {
+                from: Some(self.env().account_id()),
+                to: Some(to),
+                value,
+                namespace: *self.topic_namespace,
+            });
+        }
+
+        /// get the queued transfer `id`
+        /// return None if it doesn't exist or was already settled
+        #[ink(message)]
+        pub fn queued_transfer(&self, id: u64) -> Option<QueuedTransfer> {
+            self.queued_transfers.get(&id).copied()
+        }
+
+        /// getter for the number of blocks a queued transfer can be executed
+        /// within after its `execute_after`, 0 for no expiry
+        #[ink(message)]
+        pub fn execution_window_blocks(&self) -> BlockNumber {
+            *self.execution_window_blocks
+        }
+
+        /// set the number of blocks a queued transfer can be executed within
+        /// after its `execute_after`, 0 for no expiry
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_execution_window_blocks(&mut self, blocks: BlockNumber) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.execution_window_blocks, blocks);
+            Ok(())
+        }
+
+        /// lock `value` tokens from caller's account in an escrow sent to `to`
+        /// by execute_queued_transfer() once `delay_blocks` blocks passed
+        /// the escrowed tokens are held by the contract account
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// fail as check_sender() and check_recipient() do
+        /// return the id of the queued transfer
+        /// when success, emit `Transfer` event to the contract account
+        #[ink(message)]
+        pub fn queue_transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            delay_blocks: BlockNumber,
+        ) -> Result<u64> {
+            self.non_reentrant(|erc20| {
+                let from = erc20.env().caller();
+                erc20.check_sender(from, value)?;
+                erc20.check_recipient(from, to)?;
+
+                let id = *erc20.next_queued_transfer_id;
+                Lazy::set(&mut erc20.next_queued_transfer_id, id + 1);
+                let from_balance = erc20.balance_of(from);
+                erc20.set_balance(from, from_balance - value);
+                erc20.queued_transfers.insert(
+                    id,
+                    QueuedTransfer {
+                        from,
+                        to,
+                        value,
+                        execute_after: erc20.env().block_number().saturating_add(delay_blocks),
+                    },
+                );
+                erc20.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: Some(erc20.env().account_id()),
+                    value,
+                    namespace: *erc20.topic_namespace,
+                });
+                Ok(id)
+            })
+        }
+
+        /// send the tokens escrowed in queued transfer `id` to its recipient,
+        /// charging the fee and burn as transfer() does
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw QueuedTransferNotFound if `id` doesn't exist or was settled
+        /// throw TimelockActive before its `execute_after` block
+        /// throw TransferExpired past its execution window
+        /// fail as check_sender() and check_recipient() do, the escrowed
+        /// tokens counting as the sender's balance
+        /// when success, emit the `Transfer` events of transfer() from the
+        /// contract account, then notify the observers
+        #[ink(message)]
+        pub fn execute_queued_transfer(&mut self, id: u64) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let queued = erc20
+                    .queued_transfer(id)
+                    .ok_or(Error::QueuedTransferNotFound)?;
+                if erc20.env().block_number() < queued.execute_after {
+                    return Err(Error::TimelockActive);
+                }
+                if erc20.is_expired(&queued) {
+                    return Err(Error::TransferExpired);
+                }
+                let volume_window = erc20.check_sender_rules(queued.from, queued.value)?;
+                erc20.check_recipient(queued.from, queued.to)?;
+
+                erc20.queued_transfers.take(&id);
+                erc20.credit_transfer(
+                    queued.from,
+                    erc20.env().account_id(),
+                    queued.to,
+                    queued.value,
+                    volume_window,
+                    true,
+                );
+                Ok(())
+            })
+        }
+
+        /// refund the tokens escrowed in the expired queued transfer `id`
+        /// to its sender, callable by anyone
+        ///
+        /// throw QueuedTransferNotFound if `id` doesn't exist or was settled
+        /// throw TransferNotExpired unless past its execution window
+        /// when success, emit `Transfer` event from the contract account
+        #[ink(message)]
+        pub fn cleanup_expired(&mut self, id: u64) -> Result<()> {
+            let queued = self
+                .queued_transfer(id)
+                .ok_or(Error::QueuedTransferNotFound)?;
+            if !self.is_expired(&queued) {
+                return Err(Error::TransferNotExpired);
+            }
+
+            self.queued_transfers.take(&id);
+            self.release_escrow(queued.from, queued.value);
+            Ok(())
+        }
+
+        /// check whether `queued` is past its execution window
+        fn is_expired(&self, queued: &QueuedTransfer) -> bool {
+            let window = *self.execution_window_blocks;
+            window != 0 && self.env().block_number() > queued.execute_after.saturating_add(window)
+        }
+
+        /// claim the tokens escrowed in claim `id`, only callable by its recipient
+        ///
+        /// fail as check_recipient() does for a transfer from the claim's sender
+        /// when success, emit `Transfer` event from the contract account
+        #[ink(message)]
+        pub fn claim(&mut self, id: u64) -> Result<()> {
+            let (from, to, value) = self.get_claim(id).ok_or(Error::ClaimNotFound)?;
+            if self.env().caller() != to {
+                return Err(Error::NotClaimRecipient);
+            }
+            self.check_recipient(from, to)?;
+
+            self.claims.take(&id);
+            self.release_escrow(to, value);
+            Ok(())
+        }
+
+        /// take back the tokens escrowed in claim `id` as long as it wasn't
+        /// claimed yet, only callable by its sender
+        ///
+        /// when success, emit `Transfer` event from the contract account
+        #[ink(message)]
+        pub fn reclaim(&mut self, id: u64) -> Result<()> {
+            let (from, _, value) = self.get_claim(id).ok_or(Error::ClaimNotFound)?;
+            if self.env().caller() != from {
+                return Err(Error::NotClaimSender);
+            }
+
+            self.claims.take(&id);
+            self.release_escrow(from, value);
+            Ok(())
+        }
+
+        /// get the token amount staked by `who`
+        /// return 0 if nothing was staked
+        #[ink(message)]
+        pub fn staked_balance_of(&self, who: AccountId) -> Balance {
+            self.staked.get(&who).copied().unwrap_or(0)
+        }
+
+        /// getter for the sum of all staked token amounts
+        #[ink(message)]
+        pub fn total_staked(&self) -> Balance {
+            *self.total_staked
+        }
+
+        /// getter for the staking pause flag
+        #[ink(message)]
+        pub fn staking_paused(&self) -> bool {
+            *self.staking_paused
+        }
+
+        /// halt or resume staking and unstaking, leaving transfers alone,
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_staking_paused(&mut self, paused: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.staking_paused, paused);
+            Ok(())
+        }
+
+        /// stake `value` tokens from caller's account
+        ///
+        /// fail as check_staker() does
+        /// throw InsufficientBalance if not enough tokens on the caller's account
+        #[ink(message)]
+        pub fn stake(&mut self, value: Balance) -> Result<()> {
+            let who = self.env().caller();
+            self.inner_stake(who, who, value)
+        }
+
+        /// move `value` staked tokens back to caller's account
+        ///
+        /// fail as check_staker() does
+        /// throw InsufficientStake if the caller staked less than `value`
+        /// when success, emit `Unstaked` event
+        #[ink(message)]
+        pub fn unstake(&mut self, value: Balance) -> Result<()> {
+            let who = self.env().caller();
+            self.check_staker(who)?;
+            let staked = self.staked_balance_of(who);
+            if staked < value {
+                return Err(Error::InsufficientStake);
+            }
+
+            self.staked.insert(who, staked - value);
+            let total_staked = *self.total_staked - value;
+            Lazy::set(&mut self.total_staked, total_staked);
+            let balance = self.balance_of(who);
+            self.set_balance(who, balance + value);
+            self.env().emit_event(Unstaked { who, amount: value });
+            Ok(())
+        }
+
+        /// transfer `value` tokens to `to` as transfer() does, then stake
+        /// `stake_bps` basis points of what `to` receives on its behalf
+        /// nothing is staked if `to` is a burn address
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw InvalidParameter if `stake_bps` is above 10000
+        /// fail as check_sender() and check_recipient() do, and as
+        /// check_staker() does for `to`
+        #[ink(message)]
+        pub fn transfer_and_stake(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            stake_bps: u16,
+        ) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                if u128::from(stake_bps) > MAX_BPS {
+                    return Err(Error::InvalidParameter);
+                }
+                let from = erc20.env().caller();
+                let volume_window = erc20.check_sender(from, value)?;
+                erc20.check_recipient(from, to)?;
+                erc20.check_staker(to)?;
+
+                let received = erc20.preview_transfer(from, to, value).net_to_recipient;
+                erc20.apply_transfer(from, to, value, volume_window);
+                if erc20.is_burn_address(to) {
+                    return Ok(());
+                }
+                let staked = received - bps_of(received, MAX_BPS as u16 - stake_bps);
+                erc20.inner_stake(to, to, staked)
+            })
+        }
+
+        /// check `who` may stake or unstake, without changing any state
+        ///
+        /// throw StakingPaused while staking is paused
+        /// throw Paused while paused
+        /// throw Blacklisted if `who` is blacklisted
+        fn check_staker(&self, who: AccountId) -> Result<()> {
+            if *self.staking_paused {
+                return Err(Error::StakingPaused);
+            }
+            if self.paused() {
+                return Err(Error::Paused);
+            }
+            if self.is_blacklisted(who) {
+                return Err(Error::Blacklisted);
+            }
+            Ok(())
+        }
+
+        /// internal function for staking
+        /// move `value` tokens from `from`'s account to the stake of `who`
+        ///
+        /// when success, emit `Staked` event
+        fn inner_stake(&mut self, from: AccountId, who: AccountId, value: Balance) -> Result<()> {
+            self.check_staker(from)?;
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.set_balance(from, from_balance - value);
+            let staked = self.staked_balance_of(who);
+            self.staked.insert(who, staked + value);
+            let total_staked = *self.total_staked + value;
+            Lazy::set(&mut self.total_staked, total_staked);
+            self.env().emit_event(Staked { who, amount: value });
+            Ok(())
+        }
+
+        /// get the vesting schedule of `beneficiary`
+        /// return None if it doesn't have one
+        #[ink(message)]
+        pub fn vesting_of(&self, beneficiary: AccountId) -> Option<VestingSchedule> {
+            self.vestings.get(&beneficiary).copied()
+        }
+
+        /// get the token amount vested so far for `beneficiary`, released or not
+        #[ink(message)]
+        pub fn vested_amount(&self, beneficiary: AccountId) -> Balance {
+            let schedule = match self.vesting_of(beneficiary) {
+                Some(schedule) => schedule,
+                None => return 0,
+            };
+            let now = self.env().block_number();
+            if now < schedule.start {
+                return 0;
+            }
+
+            let elapsed = now - schedule.start;
+            if elapsed >= schedule.duration {
+                return schedule.total;
+            }
+            let (duration, elapsed) = (Balance::from(schedule.duration), Balance::from(elapsed));
+            schedule.total / duration * elapsed + schedule.total % duration * elapsed / duration
+        }
+
+        /// get the vested token amount not released yet to `beneficiary`
+        #[ink(message)]
+        pub fn releasable_amount(&self, beneficiary: AccountId) -> Balance {
+            self.vesting_of(beneficiary)
+                .map(|schedule| self.vested_amount(beneficiary) - schedule.released)
+                .unwrap_or(0)
+        }
+
+        /// get the token amount `who` can use right away: its balance plus
+        /// the vested tokens it may release, staking pays no rewards to add
+        #[ink(message)]
+        pub fn total_balance_of(&self, who: AccountId) -> Balance {
+            self.balance_of(who)
+                .saturating_add(self.releasable_amount(who))
+        }
+
+        /// lock `value` tokens from caller's account, vesting linearly to
+        /// `beneficiary` over `duration_blocks` from `start_block` on
+        /// only callable by the owner
+        ///
+        /// throw VestingExists if `beneficiary` already has a schedule
+        #[ink(message)]
+        pub fn create_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            value: Balance,
+            start_block: BlockNumber,
+            duration_blocks: BlockNumber,
+        ) -> Result<()> {
+            self.inner_create_vesting(beneficiary, value, start_block, duration_blocks, false)
+        }
+
+        /// same as create_vesting(), but the owner may later revoke the
+        /// unvested tokens with revoke_vesting()
+        #[ink(message)]
+        pub fn create_revocable_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            value: Balance,
+            start_block: BlockNumber,
+            duration_blocks: BlockNumber,
+        ) -> Result<()> {
+            self.inner_create_vesting(beneficiary, value, start_block, duration_blocks, true)
+        }
+
+        /// internal function for creating a vesting schedule
+        /// used by both create_vesting() and create_revocable_vesting()
+        fn inner_create_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            value: Balance,
+            start_block: BlockNumber,
+            duration_blocks: BlockNumber,
+            revocable: bool,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            let caller = self.env().caller();
+            if self.vestings.contains_key(&beneficiary) {
+                return Err(Error::VestingExists);
+            }
+            let caller_balance = self.balance_of(caller);
+            if caller_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.set_balance(caller, caller_balance - value);
+            self.vestings.insert(
+                beneficiary,
+                VestingSchedule {
+                    total: value,
+                    released: 0,
+                    start: start_block,
+                    duration: duration_blocks,
+                    revocable,
+                },
+            );
+            Lazy::get_mut(&mut self.storage_stats).vesting_count += 1;
+            Ok(())
+        }
+
+        /// revoke the vesting schedule of `beneficiary`: the vested tokens
+        /// not released yet are paid to it, the unvested rest returns to
+        /// the fee recipient and the schedule is deleted
+        /// only callable by the owner
+        ///
+        /// throw NoVesting if `beneficiary` doesn't have a schedule
+        /// throw VestingNotRevocable if it wasn't created revocable
+        /// when success, emit `VestingReleased`, `Transfer` to the fee recipient
+        /// if any tokens are returned, and `VestingRevoked` events
+        #[ink(message)]
+        pub fn revoke_vesting(&mut self, beneficiary: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let schedule = self.vesting_of(beneficiary).ok_or(Error::NoVesting)?;
+            if !schedule.revocable {
+                return Err(Error::VestingNotRevocable);
+            }
+            let returned = schedule.total - self.vested_amount(beneficiary);
+
+            self.inner_release(beneficiary, beneficiary)?;
+            self.vestings.take(&beneficiary);
+            Lazy::get_mut(&mut self.storage_stats).vesting_count -= 1;
+            if returned > 0 {
+                self.release_escrow(*self.fee_recipient, returned);
+            }
+            self.env().emit_event(VestingRevoked {
+                beneficiary,
+                returned,
+            });
+            Ok(())
+        }
+
+        /// release caller's vested tokens to its own account
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<()> {
+            let beneficiary = self.env().caller();
+            self.inner_release(beneficiary, beneficiary)
+        }
+
+        /// release caller's vested tokens to `recipient`'s account
+        #[ink(message)]
+        pub fn release_to(&mut self, recipient: AccountId) -> Result<()> {
+            let beneficiary = self.env().caller();
+            self.inner_release(beneficiary, recipient)
+        }
+
+        /// internal function for releasing vested tokens
+        /// used by both release() and release_to()
+        ///
+        /// throw NoVesting if `beneficiary` doesn't have a schedule
+        /// when success, emit `VestingReleased` event
+        fn inner_release(&mut self, beneficiary: AccountId, recipient: AccountId) -> Result<()> {
+            let mut schedule = self.vesting_of(beneficiary).ok_or(Error::NoVesting)?;
+            let amount = self.releasable_amount(beneficiary);
+
+            schedule.released += amount;
+            self.vestings.insert(beneficiary, schedule);
+            let recipient_balance = self.balance_of(recipient);
+            self.set_balance(recipient, recipient_balance + amount);
+            self.env().emit_event(VestingReleased {
+                beneficiary,
+                recipient,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// check whether transfers are halted, by pause() or since the block
+        /// of a scheduled pause
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            *self.paused
+                || self
+                    .scheduled_pause
+                    .is_some_and(|at_block| self.env().block_number() >= at_block)
+        }
+
+        /// getter for the block number a scheduled pause takes effect at
+        #[ink(message)]
+        pub fn scheduled_pause(&self) -> Option<BlockNumber> {
+            *self.scheduled_pause
+        }
+
+        /// getter for the flag letting the owner mint and burn while paused
+        #[ink(message)]
+        pub fn admin_ops_during_pause(&self) -> bool {
+            *self.admin_ops_during_pause
+        }
+
+        /// halt all transfers, only callable by the owner
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.paused, true);
+            Ok(())
+        }
+
+        /// halt all transfers in `blocks` blocks, giving users a warning,
+        /// only callable by the owner
+        /// replaces any pause scheduled before
+        ///
+        /// emit `PauseScheduled` event
+        #[ink(message)]
+        pub fn pause_with_delay(&mut self, blocks: BlockNumber) -> Result<()> {
+            self.ensure_owner()?;
+            let at_block = self.env().block_number().saturating_add(blocks);
+            Lazy::set(&mut self.scheduled_pause, Some(at_block));
+            self.env().emit_event(PauseScheduled { at_block });
+            Ok(())
+        }
+
+        /// resume transfers and cancel any scheduled pause, only callable by the owner
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.paused, false);
+            Lazy::set(&mut self.scheduled_pause, None);
+            Ok(())
+        }
+
+        /// getter for the flag letting the fee recipient send tokens while paused
+        #[ink(message)]
+        pub fn treasury_ops_during_pause(&self) -> bool {
+            *self.treasury_ops_during_pause
+        }
+
+        /// let the fee recipient send tokens while paused, e.g. to pay vendors
+        /// or provide liquidity, only callable by the owner
+        #[ink(message)]
+        pub fn set_treasury_ops_during_pause(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.treasury_ops_during_pause, enabled);
+            Ok(())
+        }
+
+        /// let the owner mint and burn while paused, e.g. for a migration or
+        /// to burn compromised tokens, only callable by the owner
+        #[ink(message)]
+        pub fn set_admin_ops_during_pause(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.admin_ops_during_pause, enabled);
+            Ok(())
+        }
+
+        /// getter for the flag letting holders burn while paused
+        #[ink(message)]
+        pub fn allow_burn_during_pause(&self) -> bool {
+            *self.allow_burn_during_pause
+        }
+
+        /// let holders burn their own tokens while paused so they can always exit,
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_allow_burn_during_pause(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.allow_burn_during_pause, enabled);
+            Ok(())
+        }
+
+        /// throw Paused while paused, unless `who` is the owner
+        /// and admin ops are allowed during pause
+        fn ensure_admin_op_allowed(&self, who: AccountId) -> Result<()> {
+            if self.paused() && !(*self.admin_ops_during_pause && who == *self.owner) {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        /// getter for the whitelist mode flag
+        #[ink(message)]
+        pub fn whitelist_mode(&self) -> bool {
+            *self.whitelist_mode
+        }
+
+        /// check whether `who` is on the transfer whitelist
+        #[ink(message)]
+        pub fn is_whitelisted(&self, who: AccountId) -> bool {
+            self.transfer_whitelist.contains_key(&who)
+        }
+
+        /// turn the whitelist mode on or off, while on only whitelisted accounts
+        /// and the owner can send or receive tokens, the owner can send to anyone
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_whitelist_mode(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.whitelist_mode, enabled);
+            Ok(())
+        }
+
+        /// add `who` to the transfer whitelist, only callable by the owner
+        #[ink(message)]
+        pub fn add_to_whitelist(&mut self, who: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.transfer_whitelist.insert(who, ());
+            Ok(())
+        }
+
+        /// remove `who` from the transfer whitelist, only callable by the owner
+        #[ink(message)]
+        pub fn remove_from_whitelist(&mut self, who: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.transfer_whitelist.take(&who);
+            Ok(())
+        }
+
+        /// getter for the flag requiring recipients to opt in
+        #[ink(message)]
+        pub fn require_opt_in(&self) -> bool {
+            *self.require_opt_in
+        }
+
+        /// when enabled, only the owner can transfer to accounts which didn't
+        /// opt in, only callable by the owner
+        #[ink(message)]
+        pub fn set_require_opt_in(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.require_opt_in, enabled);
+            Ok(())
+        }
+
+        /// check whether `who` opted in to receive transfers
+        #[ink(message)]
+        pub fn is_opted_in(&self, who: AccountId) -> bool {
+            self.opted_in.contains_key(&who)
+        }
+
+        /// opt in to receive transfers
+        #[ink(message)]
+        pub fn opt_in(&mut self) {
+            let who = self.env().caller();
+            self.opted_in.insert(who, ());
+        }
+
+        /// opt out of receiving transfers
+        #[ink(message)]
+        pub fn opt_out(&mut self) {
+            let who = self.env().caller();
+            self.opted_in.take(&who);
+        }
+
+        /// check whether `who` is currently blacklisted
+        #[ink(message)]
+        pub fn is_blacklisted(&self, who: AccountId) -> bool {
+            self.blacklist
+                .get(&who)
+                .map(|until_block| self.env().block_number() < *until_block)
+                .unwrap_or(false)
+        }
+
+        /// get the block number the sanction of `who` ends at
+        /// return None if it was never blacklisted
+        #[ink(message)]
+        pub fn blacklisted_until(&self, who: AccountId) -> Option<BlockNumber> {
+            self.blacklist.get(&who).copied()
+        }
+
+        /// forbid `who` to send or receive tokens until `until_block`,
+        /// `BlockNumber::MAX` blacklists it permanently
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn add_to_blacklist(&mut self, who: AccountId, until_block: BlockNumber) -> Result<()> {
+            self.ensure_owner()?;
+            let existed = self.blacklist.insert(who, until_block).is_some();
+            let stats = Lazy::get_mut(&mut self.storage_stats);
+            stats.blacklist_count = count_entry(stats.blacklist_count, existed, true);
+            Ok(())
+        }
+
+        /// lift the sanction of `who` right away, only callable by the owner
+        #[ink(message)]
+        pub fn remove_from_blacklist(&mut self, who: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let existed = self.blacklist.take(&who).is_some();
+            let stats = Lazy::get_mut(&mut self.storage_stats);
+            stats.blacklist_count = count_entry(stats.blacklist_count, existed, false);
+            Ok(())
+        }
+
+        /// getter for the contracts notified after every transfer
+        #[ink(message)]
+        pub fn observers(&self) -> Vec<AccountId> {
+            self.observers.clone()
+        }
+
+        /// notify `observer` after every transfer, only callable by the owner
+        ///
+        /// throw TooManyObservers if `MAX_OBSERVERS` are already registered
+        #[ink(message)]
+        pub fn add_observer(&mut self, observer: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if self.observers.contains(&observer) {
+                return Ok(());
+            }
+            if self.observers.len() >= MAX_OBSERVERS {
+                return Err(Error::TooManyObservers);
+            }
+            Lazy::get_mut(&mut self.observers).push(observer);
+            Ok(())
+        }
+
+        /// stop notifying `observer`, only callable by the owner
+        #[ink(message)]
+        pub fn remove_observer(&mut self, observer: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::get_mut(&mut self.observers).retain(|other| *other != observer);
+            Ok(())
+        }
+
+        /// call `notify_transfer(from, to, value)` on every observer
+        /// a failing observer is skipped so it can't block transfers
+        fn notify_observers(&self, from: AccountId, to: AccountId, value: Balance) {
+            for observer in self.observers.iter() {
+                let _ = build_call::<ink_env::DefaultEnvironment>()
+                    .callee(*observer)
+                    .gas_limit(0)
+                    .transferred_value(0)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(NOTIFY_TRANSFER_SELECTOR))
+                            .push_arg(from)
+                            .push_arg(to)
+                            .push_arg(value),
+                    )
+                    .returns::<()>()
+                    .fire();
+            }
+        }
+
+        /// getter for the airdrop Merkle root
+        #[ink(message)]
+        pub fn merkle_root(&self) -> Option<[u8; 32]> {
+            *self.merkle_root
+        }
+
+        /// getter for the token amount left for airdrop claims
+        #[ink(message)]
+        pub fn airdrop_pool(&self) -> Balance {
+            *self.airdrop_pool
+        }
+
+        /// check whether `who` already claimed its airdrop
+        #[ink(message)]
+        pub fn has_claimed_airdrop(&self, who: AccountId) -> bool {
+            self.merkle_claimed.contains_key(&who)
+        }
+
+        /// set the root of the Merkle tree of eligible (account, amount) leaves
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_merkle_root(&mut self, root: [u8; 32]) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.merkle_root, Some(root));
+            Ok(())
+        }
+
+        /// getter for the last block airdrop claims are accepted at,
+        /// 0 for no deadline
+        #[ink(message)]
+        pub fn claim_deadline(&self) -> BlockNumber {
+            *self.claim_deadline
+        }
+
+        /// accept airdrop claims up to `deadline_block` only, 0 removes the deadline
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_claim_deadline(&mut self, deadline_block: BlockNumber) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.claim_deadline, deadline_block);
+            Ok(())
+        }
+
+        /// check whether the airdrop claim deadline has passed
+        fn claim_period_ended(&self) -> bool {
+            let deadline = *self.claim_deadline;
+            deadline != 0 && self.env().block_number() > deadline
+        }
+
+        /// move the unclaimed airdrop pool back to the treasury, i.e. the fee
+        /// recipient, and remove the Merkle root, only callable by the owner
+        ///
+        /// throw ClaimPeriodActive unless the claim deadline has passed
+        /// when success, emit `Transfer` event from the contract account
+        #[ink(message)]
+        pub fn reclaim_airdrop(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.claim_period_ended() {
+                return Err(Error::ClaimPeriodActive);
+            }
+
+            self.release_escrow(*self.fee_recipient, *self.airdrop_pool);
+            Lazy::set(&mut self.airdrop_pool, 0);
+            Lazy::set(&mut self.merkle_root, None);
+            Ok(())
+        }
+
+        /// move `value` tokens from caller's account to the airdrop pool
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn fund_airdrop(&mut self, value: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            let caller = self.env().caller();
+            let caller_balance = self.balance_of(caller);
+            if caller_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.set_balance(caller, caller_balance - value);
+            let airdrop_pool = *self.airdrop_pool + value;
+            Lazy::set(&mut self.airdrop_pool, airdrop_pool);
+            Ok(())
+        }
+
+        /// claim the `amount` tokens airdropped to the caller from the pool,
+        /// `proof` holds the sibling hashes from the caller's leaf up to the root
+        ///
+        /// throw ClaimPeriodEnded if the claim deadline has passed
+        /// throw InvalidProof if (caller, amount) isn't a leaf of the Merkle tree
+        /// throw AlreadyClaimed if the caller claimed before
+        /// when success, emit `AirdropClaimed` event
+        #[ink(message)]
+        pub fn claim_merkle(&mut self, amount: Balance, proof: Vec<[u8; 32]>) -> Result<()> {
+            if self.claim_period_ended() {
+                return Err(Error::ClaimPeriodEnded);
+            }
+            let who = self.env().caller();
+            let root = self.merkle_root().ok_or(Error::NoMerkleRoot)?;
+            if self.has_claimed_airdrop(who) {
+                return Err(Error::AlreadyClaimed);
+            }
+            let computed_root = proof
+                .iter()
+                .fold(merkle_leaf(who, amount), |hash, sibling| {
+                    hash_pair(&hash, sibling)
+                });
+            if computed_root != root {
+                return Err(Error::InvalidProof);
+            }
+            let airdrop_pool = self
+                .airdrop_pool
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientAirdropPool)?;
+
+            Lazy::set(&mut self.airdrop_pool, airdrop_pool);
+            self.merkle_claimed.insert(who, ());
+            let balance = self.balance_of(who);
+            self.set_balance(who, balance + amount);
+            self.env().emit_event(AirdropClaimed { who, amount });
+            Ok(())
+        }
+
+        /// check whether `who` has been granted `role`
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, who: AccountId) -> bool {
+            self.roles.contains_key(&(role, who))
+        }
+
+        /// grant `role` to `who`, only callable by the owner
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, who: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.roles.insert((role, who), ());
+            Ok(())
+        }
+
+        /// revoke `role` from `who`, only callable by the owner
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, who: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.roles.take(&(role, who));
+            Ok(())
+        }
+
+        /// get the token amount `burner` may still burn from the treasury
+        #[ink(message)]
+        pub fn burn_quota(&self, burner: AccountId) -> Balance {
+            self.burn_quotas.get(&burner).copied().unwrap_or(0)
+        }
+
+        /// set the token amount `burner` may burn from the treasury,
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_burn_quota(&mut self, burner: AccountId, quota: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.burn_quotas.insert(burner, quota);
+            Ok(())
+        }
+
+        /// burn `value` tokens from the treasury, i.e. the fee recipient,
+        /// out of caller's burn quota
+        ///
+        /// throw MissingRole if the caller hasn't the burner role
+        /// throw BurnerQuotaExceeded if `value` is above caller's quota
+        /// throw InsufficientBalance if not enough tokens on the treasury
+        /// when success, emit `Transfer` event
+        #[ink(message)]
+        pub fn burn_from_treasury(&mut self, value: Balance) -> Result<()> {
+            let burner = self.env().caller();
+            if !self.has_role(BURNER_ROLE, burner) {
+                return Err(Error::MissingRole);
+            }
+            let quota = self.burn_quota(burner);
+            if value > quota {
+                return Err(Error::BurnerQuotaExceeded);
+            }
+
+            self.inner_burn(*self.fee_recipient, value)?;
+            self.burn_quotas.insert(burner, quota - value);
+            Ok(())
+        }
+
+        /// getter for the recovery guardian and the recovery delay in blocks
+        #[ink(message)]
+        pub fn recovery_guardian(&self) -> (Option<AccountId>, BlockNumber) {
+            (*self.recovery_guardian, *self.recovery_delay_blocks)
+        }
+
+        /// getter for the proposed new owner and the block the recovery
+        /// can be executed at
+        #[ink(message)]
+        pub fn pending_recovery(&self) -> Option<(AccountId, BlockNumber)> {
+            *self.pending_recovery
+        }
+
+        /// let `guardian` propose a new owner, who takes over `delay_blocks`
+        /// later unless the owner cancels, None removes the guardian
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_recovery_guardian(
+            &mut self,
+            guardian: Option<AccountId>,
+            delay_blocks: BlockNumber,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.recovery_guardian, guardian);
+            Lazy::set(&mut self.recovery_delay_blocks, delay_blocks);
+            Ok(())
+        }
+
+        /// propose `new_owner` as the owner once the recovery delay elapsed,
+        /// replacing any pending proposal, only callable by the guardian
+        ///
+        /// throw NotGuardian if the caller isn't the recovery guardian
+        #[ink(message)]
+        pub fn propose_owner_recovery(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_guardian()?;
+            let executable_at = self
+                .env()
+                .block_number()
+                .saturating_add(*self.recovery_delay_blocks);
+            Lazy::set(&mut self.pending_recovery, Some((new_owner, executable_at)));
+            Ok(())
+        }
+
+        /// make the proposed account the owner, only callable by the guardian
+        ///
+        /// throw NotGuardian if the caller isn't the recovery guardian
+        /// throw NoPendingRecovery if no recovery was proposed
+        /// throw RecoveryDelayActive if the recovery delay hasn't elapsed yet
+        #[ink(message)]
+        pub fn execute_owner_recovery(&mut self) -> Result<()> {
+            self.ensure_guardian()?;
+            let (new_owner, executable_at) =
+                self.pending_recovery().ok_or(Error::NoPendingRecovery)?;
+            if self.env().block_number() < executable_at {
+                return Err(Error::RecoveryDelayActive);
+            }
+            Lazy::set(&mut self.owner, new_owner);
+            Lazy::set(&mut self.pending_recovery, None);
+            Ok(())
+        }
+
+        /// drop the pending recovery, proving the owner key isn't lost
+        /// only callable by the owner
+        ///
+        /// throw NoPendingRecovery if no recovery was proposed
+        #[ink(message)]
+        pub fn cancel_owner_recovery(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            if self.pending_recovery.is_none() {
+                return Err(Error::NoPendingRecovery);
+            }
+            Lazy::set(&mut self.pending_recovery, None);
+            Ok(())
+        }
+
+        /// get the guardian of `who` and the blocks of inactivity after which
+        /// it can move `who`'s tokens
+        /// return None if `who` has no guardian
+        #[ink(message)]
+        pub fn account_guardian(&self, who: AccountId) -> Option<(AccountId, BlockNumber)> {
+            self.account_guardians.get(&who).copied()
+        }
+
+        /// get the block number of the last activity of `who`, tracked while
+        /// it has a guardian
+        #[ink(message)]
+        pub fn last_activity_block(&self, who: AccountId) -> BlockNumber {
+            self.last_activity_blocks.get(&who).copied().unwrap_or(0)
+        }
+
+        /// let `guardian` move all of the caller's tokens once the caller was
+        /// inactive for `inactivity_blocks`, None removes the guardian
+        #[ink(message)]
+        pub fn set_account_guardian(
+            &mut self,
+            guardian: Option<AccountId>,
+            inactivity_blocks: BlockNumber,
+        ) {
+            let who = self.env().caller();
+            match guardian {
+                Some(guardian) => {
+                    self.account_guardians
+                        .insert(who, (guardian, inactivity_blocks));
+                    self.record_activity(who);
+                }
+                None => {
+                    self.account_guardians.take(&who);
+                    self.last_activity_blocks.take(&who);
+                }
+            }
+        }
+
+        /// move the whole balance of `owner` to `to` as a transfer of `owner`,
+        /// only callable by its guardian once `owner` was inactive long enough
+        ///
+        /// throw NotGuardian if the caller isn't `owner`'s guardian
+        /// throw RecoveryDelayActive if `owner` was active within its
+        /// inactivity blocks
+        /// fail as transfer() does otherwise
+        #[ink(message)]
+        pub fn guardian_recover(&mut self, owner: AccountId, to: AccountId) -> Result<()> {
+            let (_, inactivity_blocks) = self
+                .account_guardian(owner)
+                .filter(|(guardian, _)| *guardian == self.env().caller())
+                .ok_or(Error::NotGuardian)?;
+            let inactive_from = self
+                .last_activity_block(owner)
+                .saturating_add(inactivity_blocks);
+            if self.env().block_number() < inactive_from {
+                return Err(Error::RecoveryDelayActive);
+            }
+            let value = self.balance_of(owner);
+            self.inner_transfer(owner, to, value)
+        }
+
+        /// record the current block as the last activity of `who` if it has
+        /// a guardian
+        fn record_activity(&mut self, who: AccountId) {
+            if self.account_guardians.contains_key(&who) {
+                let now = self.env().block_number();
+                self.last_activity_blocks.insert(who, now);
+            }
+        }
+
+        /// move the balances of up to `limit` holders to `new_contract` through
+        /// its `receive_migration(entries)` message, zeroing them here and
+        /// reducing the total supply, only callable by the owner
+        /// call it again until no holder is left, only liquid balances are moved
+        ///
+        /// once the total supply and the v2 supply are zero and `terminate` is
+        /// set, terminate the contract and send its native balance to the owner
+        /// tokens staked, vesting, escrowed or in the airdrop pool keep it alive
+        /// until they are back on liquid balances and migrated
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw CallFailed if `receive_migration()` fails, nothing is moved then
+        /// when success, emit a `Transfer` event to None per moved balance
+        #[ink(message)]
+        pub fn migrate_to(
+            &mut self,
+            new_contract: AccountId,
+            limit: u32,
+            terminate: bool,
+        ) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                erc20.ensure_owner()?;
+                let entries: Vec<(AccountId, Balance)> = erc20
+                    .balances
+                    .keys()
+                    .take(limit as usize)
+                    .map(|who| (*who, erc20.balance_of(*who)))
+                    .collect();
+
+                if !entries.is_empty() {
+                    build_call::<ink_env::DefaultEnvironment>()
+                        .callee(new_contract)
+                        .gas_limit(0)
+                        .transferred_value(0)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(RECEIVE_MIGRATION_SELECTOR))
+                                .push_arg(&entries),
+                        )
+                        .returns::<()>()
+                        .fire()
+                        .map_err(|_| Error::CallFailed)?;
+
+                    let mut moved: Balance = 0;
+                    for (who, value) in entries {
+                        erc20.set_balance(who, 0);
+                        moved += value;
+                        erc20.env().emit_event(Transfer {
+                            from: Some(who),
+                            to: None,
+                            value,
+                            namespace: *erc20.topic_namespace,
+                        });
+                    }
+                    let mut total_supply = *erc20.total_supply - moved;
+                    if *erc20.reflected_supply == 0 {
+                        // the rounding dust of the reflections belongs to no balance
+                        total_supply -= *erc20.reflected_tokens;
+                        Lazy::set(&mut erc20.reflected_tokens, 0);
+                    }
+                    erc20.set_total_supply(total_supply);
+                }
+
+                if terminate && *erc20.total_supply == 0 && *erc20.v2_total_supply == 0 {
+                    erc20.env().terminate_contract(erc20.env().caller());
+                }
+                Ok(())
+            })
+        }
+
+        /// throw NotGuardian if the caller isn't the recovery guardian
+        fn ensure_guardian(&self) -> Result<()> {
+            if *self.recovery_guardian != Some(self.env().caller()) {
+                return Err(Error::NotGuardian);
+            }
+            Ok(())
+        }
+
+        /// throw NotOwner if the caller isn't the owner
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        /// getter for the governor account
+        #[ink(message)]
+        pub fn governor(&self) -> AccountId {
+            *self.governor
+        }
+
+        /// getter for the account receiving the transfer fees
+        #[ink(message)]
+        pub fn fee_recipient(&self) -> AccountId {
+            *self.fee_recipient
+        }
+
+        /// getter for the rounding direction of fractional fees
+        #[ink(message)]
+        pub fn fee_rounding(&self) -> RoundingMode {
+            *self.fee_rounding
+        }
+
+        /// make `new_treasury` the fee recipient and move the whole balance
+        /// of the old one to it, only callable by the owner
+        ///
+        /// throw Paused while paused, unless admin ops are allowed during pause
+        /// throw Blacklisted if the old or the new treasury is blacklisted
+        /// when success, emit `FeeRecipientChanged` and `Transfer` events
+        #[ink(message)]
+        pub fn migrate_treasury(&mut self, new_treasury: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_admin_op_allowed(self.env().caller())?;
+            let old_treasury = *self.fee_recipient;
+            if self.is_blacklisted(old_treasury) || self.is_blacklisted(new_treasury) {
+                return Err(Error::Blacklisted);
+            }
+
+            let value = self.balance_of(old_treasury);
+            if old_treasury != new_treasury {
+                self.set_balance(old_treasury, 0);
+                let new_balance = self.balance_of(new_treasury);
+                self.set_balance(new_treasury, new_balance + value);
+            }
+            Lazy::set(&mut self.fee_recipient, new_treasury);
+            self.env().emit_event(FeeRecipientChanged {
+                old: old_treasury,
+                new: new_treasury,
+            });
+            self.env().emit_event(Transfer {
+                from: Some(old_treasury),
+                to: Some(new_treasury),
+                value,
+                namespace: *self.topic_namespace,
+            });
+            Ok(())
+        }
+
+        /// get the current value of the governed parameter `key`
+        #[ink(message)]
+        pub fn parameter(&self, key: ParamKey) -> u128 {
+            match key {
+                ParamKey::FeeBps => u128::from(*self.fee_bps),
+                ParamKey::MaxTxAmount => *self.max_tx_amount,
+                ParamKey::CooldownBlocks => u128::from(*self.cooldown_blocks),
+                ParamKey::BurnBps => u128::from(*self.burn_bps),
+                ParamKey::MinFee => *self.min_fee,
+                ParamKey::TaxBurnShareBps => u128::from(*self.tax_burn_share_bps),
+                ParamKey::ReflectionBps => u128::from(*self.reflection_bps),
+            }
+        }
+
+        /// queue setting the governed parameter `key` to `value`, replacing any
+        /// change of `key` queued before, applied by apply_parameter() once
+        /// the parameter delay passed
+        /// only callable by the governor
+        ///
+        /// throw InvalidParameter if `value` is out of range for `key`,
+        /// or sets a reflection tax on a token with a cap of 2^64 or more
+        /// when success, emit `ParameterQueued` event
+        #[ink(message)]
+        pub fn set_parameter(&mut self, key: ParamKey, value: u128) -> Result<()> {
+            if self.env().caller() != *self.governor {
+                return Err(Error::NotGovernor);
+            }
+            let max = match key {
+                ParamKey::FeeBps
+                | ParamKey::BurnBps
+                | ParamKey::TaxBurnShareBps
+                | ParamKey::ReflectionBps => MAX_BPS,
+                ParamKey::CooldownBlocks => u128::from(BlockNumber::MAX),
+                ParamKey::MaxTxAmount | ParamKey::MinFee => u128::MAX,
+            };
+            if value > max {
+                return Err(Error::InvalidParameter);
+            }
+            // reflections need a rate above the most tokens there can be
+            if key == ParamKey::ReflectionBps && value > 0 && self.reflection_rate() <= self.cap() {
+                return Err(Error::InvalidParameter);
+            }
+
+            let effective_block = self
+                .env()
+                .block_number()
+                .saturating_add(*self.parameter_delay_blocks);
+            self.pending_parameters
+                .insert(key, (value, effective_block));
+            self.env().emit_event(ParameterQueued {
+                key,
+                value,
+                effective_block,
+            });
+            Ok(())
+        }
+
+        /// get the queued change of the governed parameter `key` as
+        /// (value, block it can be applied from)
+        /// return None if no change is queued
+        #[ink(message)]
+        pub fn pending_parameter(&self, key: ParamKey) -> Option<(u128, BlockNumber)> {
+            self.pending_parameters.get(&key).copied()
+        }
+
+        /// apply the queued change of the governed parameter `key`,
+        /// callable by anyone once its delay passed
+        ///
+        /// throw NoPendingParameter if no change of `key` is queued
+        /// throw TimelockActive before the block it can be applied from
+        /// when success, emit `ParameterChanged` event
+        #[ink(message)]
+        pub fn apply_parameter(&mut self, key: ParamKey) -> Result<()> {
+            let (value, effective_block) = self
+                .pending_parameter(key)
+                .ok_or(Error::NoPendingParameter)?;
+            if self.env().block_number() < effective_block {
+                return Err(Error::TimelockActive);
+            }
+
+            self.pending_parameters.take(&key);
+            self.write_parameter(key, value);
+            Ok(())
+        }
+
+        /// getter for the number of blocks a parameter change waits before
+        /// it can be applied
+        #[ink(message)]
+        pub fn parameter_delay_blocks(&self) -> BlockNumber {
+            *self.parameter_delay_blocks
+        }
+
+        /// set the number of blocks a parameter change waits before it can
+        /// be applied
+        /// only callable by the governor, changes queued before keep their block
+        ///
+        /// throw InvalidParameter if `blocks` is below the min parameter delay
+        /// or above the max one
+        #[ink(message)]
+        pub fn set_parameter_delay_blocks(&mut self, blocks: BlockNumber) -> Result<()> {
+            if self.env().caller() != *self.governor {
+                return Err(Error::NotGovernor);
+            }
+            if !(MIN_PARAMETER_DELAY_BLOCKS..=MAX_PARAMETER_DELAY_BLOCKS).contains(&blocks) {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.parameter_delay_blocks, blocks);
+            Ok(())
+        }
+
+        /// internal function for writing an in-range governed parameter
+        /// used by apply_parameter()
+        ///
+        /// emit `ParameterChanged` event
+        fn write_parameter(&mut self, key: ParamKey, value: u128) {
+            let old = self.parameter(key);
+            match key {
+                ParamKey::FeeBps => Lazy::set(&mut self.fee_bps, value as u16),
+                ParamKey::MaxTxAmount => Lazy::set(&mut self.max_tx_amount, value),
+                ParamKey::CooldownBlocks => {
+                    Lazy::set(&mut self.cooldown_blocks, value as BlockNumber)
+                }
+                ParamKey::BurnBps => Lazy::set(&mut self.burn_bps, value as u16),
+                ParamKey::MinFee => Lazy::set(&mut self.min_fee, value),
+                ParamKey::TaxBurnShareBps => Lazy::set(&mut self.tax_burn_share_bps, value as u16),
+                ParamKey::ReflectionBps => Lazy::set(&mut self.reflection_bps, value as u16),
+            }
+            self.env().emit_event(ParameterChanged {
+                key,
+                old,
+                new: value,
+            });
+        }
+
+        /// getter for the max volume per window and the window length in blocks
+        #[ink(message)]
+        pub fn volume_cap(&self) -> (Balance, BlockNumber) {
+            (*self.max_volume_per_window, *self.volume_window_blocks)
+        }
+
+        /// get the token amount transferred so far in the current volume window
+        #[ink(message)]
+        pub fn volume_this_window(&self) -> Balance {
+            self.current_volume_window().1
+        }
+
+        /// limit the token amount all non-owner transfers can move within
+        /// `window_blocks` blocks to `max_volume`, 0 removes the limit
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_volume_cap(
+            &mut self,
+            max_volume: Balance,
+            window_blocks: BlockNumber,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if max_volume != 0 && window_blocks == 0 {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.max_volume_per_window, max_volume);
+            Lazy::set(&mut self.volume_window_blocks, window_blocks);
+            let now = self.env().block_number();
+            Lazy::set(&mut self.volume_this_window, 0);
+            Lazy::set(&mut self.window_start, now);
+            Ok(())
+        }
+
+        /// get the (start block, transferred volume) of the current volume window,
+        /// rolling over to a fresh window once the stored one elapsed
+        fn current_volume_window(&self) -> (BlockNumber, Balance) {
+            let now = self.env().block_number();
+            let window_start = *self.window_start;
+            if now >= window_start.saturating_add(*self.volume_window_blocks) {
+                (now, 0)
+            } else {
+                (window_start, *self.volume_this_window)
+            }
+        }
+
+        /// getter for the block trading was enabled at
+        #[ink(message)]
+        pub fn launch_block(&self) -> Option<BlockNumber> {
+            *self.launch_block
+        }
+
+        /// getter for the sniper tax window in blocks and its initial basis points
+        #[ink(message)]
+        pub fn sniper_tax(&self) -> (BlockNumber, u16) {
+            (*self.sniper_tax_blocks, *self.sniper_tax_bps)
+        }
+
+        /// tax transfers by `tax_bps` basis points at launch, decaying linearly
+        /// to nothing `tax_blocks` after it, only callable by the owner
+        ///
+        /// throw InvalidParameter if `tax_bps` is above 10000
+        #[ink(message)]
+        pub fn set_sniper_tax(&mut self, tax_blocks: BlockNumber, tax_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if u128::from(tax_bps) > MAX_BPS {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.sniper_tax_blocks, tax_blocks);
+            Lazy::set(&mut self.sniper_tax_bps, tax_bps);
+            Ok(())
+        }
+
+        /// mark the current block as the launch, starting the sniper tax window
+        /// only callable by the owner
+        ///
+        /// throw TradingAlreadyEnabled if called before
+        #[ink(message)]
+        pub fn enable_trading(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            if self.launch_block.is_some() {
+                return Err(Error::TradingAlreadyEnabled);
+            }
+            let now = self.env().block_number();
+            Lazy::set(&mut self.launch_block, Some(now));
+            Ok(())
+        }
+
+        /// get the sniper tax of the current block in basis points
+        fn current_sniper_tax_bps(&self) -> u16 {
+            let tax_blocks = *self.sniper_tax_blocks;
+            let elapsed = match *self.launch_block {
+                Some(launch_block) => self.env().block_number() - launch_block,
+                None => return 0,
+            };
+            if elapsed >= tax_blocks {
+                return 0;
+            }
+            let remaining = u128::from(tax_blocks - elapsed);
+            (u128::from(*self.sniper_tax_bps) * remaining / u128::from(tax_blocks)) as u16
+        }
+
+        /// get the number of transfers `who` sent
+        #[ink(message)]
+        pub fn transfer_count(&self, who: AccountId) -> u32 {
+            self.transfer_counts.get(&who).copied().unwrap_or(0)
+        }
+
+        /// getter for the number of first transfers of an account which pay no fee
+        #[ink(message)]
+        pub fn free_transfers_for_new(&self) -> u32 {
+            *self.free_transfers_for_new
+        }
+
+        /// let the first `count` transfers of every account pay no fee,
+        /// only callable by the owner
+        #[ink(message)]
+        pub fn set_free_transfers_for_new(&mut self, count: u32) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.free_transfers_for_new, count);
+            Ok(())
+        }
+
+        /// get the scheduled (start block, end block) windows without transfer fees
+        #[ink(message)]
+        pub fn fee_holidays(&self) -> Vec<(BlockNumber, BlockNumber)> {
+            self.fee_holidays.clone()
+        }
+
+        /// schedule a window from `start_block` to `end_block` included during
+        /// which transfers pay no fee, only callable by the owner
+        /// the windows over are dropped
+        ///
+        /// throw InvalidParameter if `end_block` is before `start_block` or
+        /// the current block, the window overlaps another one or too many
+        /// windows are scheduled
+        #[ink(message)]
+        pub fn schedule_fee_holiday(
+            &mut self,
+            start_block: BlockNumber,
+            end_block: BlockNumber,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            let now = self.env().block_number();
+            if end_block < start_block || end_block < now {
+                return Err(Error::InvalidParameter);
+            }
+            let mut holidays: Vec<_> = self
+                .fee_holidays
+                .iter()
+                .copied()
+                .filter(|(_, end)| *end >= now)
+                .collect();
+            let overlaps = holidays
+                .iter()
+                .any(|(start, end)| start_block <= *end && *start <= end_block);
+            if overlaps || holidays.len() == MAX_FEE_HOLIDAYS {
+                return Err(Error::InvalidParameter);
+            }
+            holidays.push((start_block, end_block));
+            Lazy::set(&mut self.fee_holidays, holidays);
+            Ok(())
+        }
+
+        /// get the (min transferred value, fee in basis points) fee brackets
+        #[ink(message)]
+        pub fn fee_brackets(&self) -> Vec<(Balance, u16)> {
+            self.fee_brackets.clone()
+        }
+
+        /// charge transfers of at least each bracket's min value the bracket's
+        /// fee instead of the flat fee, only callable by the owner
+        /// no brackets means the flat fee for every transfer
+        ///
+        /// throw InvalidParameter if the min values aren't strictly ascending,
+        /// a fee is above 10000 or there are too many brackets
+        #[ink(message)]
+        pub fn set_fee_brackets(&mut self, brackets: Vec<(Balance, u16)>) -> Result<()> {
+            self.ensure_owner()?;
+            let ascending = brackets.windows(2).all(|pair| pair[0].0 < pair[1].0);
+            let in_bounds = brackets.iter().all(|(_, bps)| u128::from(*bps) <= MAX_BPS);
+            if !ascending || !in_bounds || brackets.len() > MAX_FEE_BRACKETS {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.fee_brackets, brackets);
+            Ok(())
+        }
+
+        /// get the fee in basis points of a transfer of `value` tokens,
+        /// from the highest bracket it reaches or the flat fee below them
+        fn fee_bps_for(&self, value: Balance) -> u16 {
+            self.fee_brackets
+                .iter()
+                .rev()
+                .find(|(min_value, _)| value >= *min_value)
+                .map_or(*self.fee_bps, |(_, bps)| *bps)
+        }
+
+        /// check whether the current block is within a fee holiday
+        fn in_fee_holiday(&self) -> bool {
+            let now = self.env().block_number();
+            self.fee_holidays
+                .iter()
+                .any(|(start, end)| *start <= now && now <= *end)
+        }
+
+        /// get how a transfer of `value` tokens from `from` to `to` would be split
+        /// between the recipient, the fee recipient and the burn
+        /// transfers from or to the fee recipient are exempted, and the first
+        /// free_transfers_for_new() transfers of `from` or during a fee holiday
+        /// pay no fee
+        ///
+        /// only computes the amounts, doesn't check the transfer would succeed
+        #[ink(message)]
+        pub fn preview_transfer(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> TransferPreview {
+            if to == *self.fee_recipient {
+                return TransferPreview {
+                    net_to_recipient: value,
+                    ..Default::default()
+                };
+            }
+            self.preview_charges(from, value)
+        }
+
+        /// same as preview_transfer() to any recipient but the fee recipient
+        fn preview_charges(&self, from: AccountId, value: Balance) -> TransferPreview {
+            if from == *self.fee_recipient {
+                return TransferPreview {
+                    net_to_recipient: value,
+                    ..Default::default()
+                };
+            }
+
+            let fee_bps = self.fee_bps_for(value);
+            let fee = if fee_bps == 0
+                || self.transfer_count(from) < *self.free_transfers_for_new
+                || self.in_fee_holiday()
+            {
+                0
+            } else {
+                let fee = match *self.fee_rounding {
+                    RoundingMode::TowardTreasury => bps_of_rounded_up(value, fee_bps),
+                    RoundingMode::TowardSender => bps_of(value, fee_bps),
+                };
+                // small transfers pay at least the min fee, at most all of `value`
+                fee.max(*self.min_fee).min(value)
+            };
+            let burn = bps_of(value, *self.burn_bps).min(value - fee);
+            let tax = bps_of(value, self.current_sniper_tax_bps()).min(value - fee - burn);
+            // nothing to reflect until some holder takes part in reflections,
+            // nor once the rate would fall to the reflected tokens, so the
+            // rounded down balances can't sum up to more than them
+            let reflection = bps_of(value, *self.reflection_bps).min(value - fee - burn - tax);
+            let reflected_tokens = *self.reflected_tokens + reflection;
+            let reflection = match reflected_tokens.checked_mul(reflected_tokens) {
+                Some(square) if square < *self.reflected_supply => reflection,
+                _ => 0,
+            };
+            // part of the fee and tax is burned, the rest goes to the fee recipient
+            let fee_burn = bps_of(fee, *self.tax_burn_share_bps);
+            let tax_burn = bps_of(tax, *self.tax_burn_share_bps);
+            TransferPreview {
+                net_to_recipient: value - fee - burn - tax - reflection,
+                fee: fee - fee_burn,
+                burn: burn + fee_burn + tax_burn,
+                tax: tax - tax_burn,
+                reflection,
+            }
+        }
+
+        /// internal function for transferring on the behalf of `from`
+        /// `spender`'s allowance is only consumed if the transfer succeeds,
+        /// an allowance of `Balance::MAX` is unlimited and never consumed
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// throw SpenderNotAccepted if acceptance is required and `spender`
+        /// didn't accept to spend `from`'s tokens
+        /// throw InsufficientApproval if `spender` is allowed less than `value`
+        /// throw PeriodLimitExceeded if `spender`'s spending limit is reached
+        /// return the remaining allowance
+        fn inner_transfer_from(
+            &mut self,
+            spender: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<Balance> {
+            self.non_reentrant(|erc20| erc20.unguarded_transfer_from(spender, from, to, value))
+        }
+
+        /// same as inner_transfer_from() without the reentrancy guard
+        fn unguarded_transfer_from(
+            &mut self,
+            spender: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<Balance> {
+            if *self.require_spender_acceptance && !self.is_spender_accepted(from, spender) {
+                return Err(Error::SpenderNotAccepted);
+            }
+            let allowance = self.allowance(from, spender);
+
+            if allowance < value {
+                return Err(Error::InsufficientApproval);
+            }
+            let limit = self.spend_within_limit(from, spender, value)?;
+
+            let volume_window = self.check_sender(from, value)?;
+            self.check_recipient(from, to)?;
+            if allowance != Balance::MAX {
+                self.set_allowance(from, spender, allowance - value);
+            }
+            if let Some(limit) = limit {
+                self.spending_limits.insert((from, spender), limit);
+            }
+            // may clear the allowance if `from` gets emptied
+            self.apply_transfer(from, to, value, volume_window);
+            Ok(self.allowance(from, spender))
+        }
+
+        /// getter for the max allowance of a single approval
+        #[ink(message)]
+        pub fn max_single_approval(&self) -> Balance {
+            *self.max_single_approval
+        }
+
+        /// reject approvals above `max` other than unlimited ones,
+        /// 0 disables the limit, only callable by the owner
+        #[ink(message)]
+        pub fn set_max_single_approval(&mut self, max: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.max_single_approval, max);
+            Ok(())
+        }
+
+        /// getter for the max number of distinct spenders an account can approve
+        #[ink(message)]
+        pub fn max_spenders_per_owner(&self) -> u32 {
+            *self.max_spenders_per_owner
+        }
+
+        /// set the max number of distinct spenders an account can approve,
+        /// only callable by the owner
+        /// accounts above it keep their spenders but can't add new ones
+        #[ink(message)]
+        pub fn set_max_spenders_per_owner(&mut self, max: u32) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.max_spenders_per_owner, max);
+            Ok(())
+        }
+
+        /// throw ApprovalTooLarge if `value` is above the max single approval,
+        /// unless it is the unlimited `Balance::MAX`
+        /// throw TooManySpenders if `spender` would be a new spender of `owner`
+        /// beyond the max spenders per owner
+        fn check_approval(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let max = *self.max_single_approval;
+            if max != 0 && value > max && value != Balance::MAX {
+                return Err(Error::ApprovalTooLarge);
+            }
+            if value > 0 {
+                if let Some(spenders) = self.owner_spenders.get(&owner) {
+                    if !spenders.contains(&spender)
+                        && spenders.len() >= *self.max_spenders_per_owner as usize
+                    {
+                        return Err(Error::TooManySpenders);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// internal function for approve
+        /// used by both approve() and approve_with_label()
+        ///
+        /// fail as check_approval() does
+        fn inner_approve(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.check_approval(owner, spender, value)?;
+            self.write_approval(owner, spender, value);
+            Ok(())
+        }
+
+        /// store the approval of `spender` for `value` of `owner`'s tokens
+        /// once checked, dropping any limit, regeneration or decay
+        fn write_approval(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            self.record_activity(owner);
+            self.spending_limits.take(&(owner, spender));
+            self.regenerations.take(&(owner, spender));
+            self.decays.take(&(owner, spender));
+            self.set_allowance(owner, spender, value);
+            let mut spenders = self.owner_spenders.get(&owner).cloned().unwrap_or_default();
+            if value > 0 && !spenders.contains(&spender) {
+                spenders.push(spender);
+                self.owner_spenders.insert(owner, spenders);
+            }
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            if value == 0 {
+                self.env().emit_event(ApprovalRevoked { owner, spender });
+            }
+        }
+
+        /// get up to `limit` stored allowances from the `start`th one,
+        /// to import them into a new deployment, only callable by the owner
+        #[ink(message)]
+        pub fn export_allowances(
+            &self,
+            start: u32,
+            limit: u32,
+        ) -> Result<Vec<((AccountId, AccountId), Balance)>> {
+            self.ensure_owner()?;
+            Ok(self
+                .allowances
+                .iter()
+                .skip(start as usize)
+                .take(limit as usize)
+                .map(|(key, value)| (*key, *value))
+                .collect())
+        }
+
+        /// set the allowances exported from a previous deployment
+        /// only callable by the owner before trading is enabled
+        ///
+        /// throw TradingAlreadyEnabled once enable_trading() was called
+        /// fail as check_approval() does for any entry, checked against
+        /// the allowances before the import
+        /// when success, emit an `Approval` event per allowance
+        #[ink(message)]
+        pub fn import_allowances(
+            &mut self,
+            entries: Vec<((AccountId, AccountId), Balance)>,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if self.launch_block.is_some() {
+                return Err(Error::TradingAlreadyEnabled);
+            }
+            for ((owner, spender), value) in &entries {
+                self.check_approval(*owner, *spender, *value)?;
+            }
+            for ((owner, spender), value) in entries {
+                self.write_approval(owner, spender, value);
+            }
+            Ok(())
+        }
+
+        /// store the allowance of `spender` over `owner`'s tokens as of now,
+        /// a zero allowance is removed rather than stored, and `spender`
+        /// stops counting as a spender of `owner` unless it regenerates
+        fn set_allowance(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            let now = self.env().block_number();
+            let regenerates = match self.regenerations.get_mut(&(owner, spender)) {
+                Some(regeneration) => {
+                    regeneration.last_update = now;
+                    true
+                }
+                None => false,
+            };
+            if value == 0 && !regenerates {
+                if let Some(spenders) = self.owner_spenders.get_mut(&owner) {
+                    if let Some(index) = spenders.iter().position(|other| *other == spender) {
+                        spenders.swap_remove(index);
+                    }
+                }
+            }
+            let existed = if value == 0 {
+                self.allowances.take(&(owner, spender)).is_some()
+            } else {
+                self.allowances.insert((owner, spender), value).is_some()
+            };
+            let stats = Lazy::get_mut(&mut self.storage_stats);
+            stats.allowance_count = count_entry(stats.allowance_count, existed, value != 0);
+        }
+
+        /// zero all the allowances granted by `owner`, dropping their limits,
+        /// regenerations and decays so they can't grow back
+        /// emit an `Approval` event for each of them
+        fn clear_allowances(&mut self, owner: AccountId) {
+            let spenders = self.owner_spenders.take(&owner).unwrap_or_default();
+            for spender in spenders {
+                self.spending_limits.take(&(owner, spender));
+                self.regenerations.take(&(owner, spender));
+                self.decays.take(&(owner, spender));
+                self.set_allowance(owner, spender, 0);
+                self.env().emit_event(Approval {
+                    owner,
+                    spender,
+                    value: 0,
+                });
+            }
+        }
+
+        /// transfer `value` tokens from caller's account split among `recipients`
+        /// by their basis points, which must sum up to 10000
+        /// the rounding leftover goes to the last recipient so exactly `value` is sent
+        ///
+        /// the split counts as a single transfer of `value` for the sender checks
+        /// and the fee, burn and tax, its net split by the same basis points
+        /// throw Reentrancy from within the external calls of another message
+        /// throw InvalidSplit if the basis points don't sum up to 10000
+        #[ink(message)]
+        pub fn split_transfer(
+            &mut self,
+            value: Balance,
+            recipients: Vec<(AccountId, u16)>,
+        ) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                let from = erc20.env().caller();
+                let total_bps: u128 = recipients.iter().map(|(_, bps)| u128::from(*bps)).sum();
+                if total_bps != MAX_BPS {
+                    return Err(Error::InvalidSplit);
+                }
+                let volume_window = erc20.check_sender(from, value)?;
+                for (to, _) in recipients.iter() {
+                    erc20.check_recipient(from, *to)?;
+                }
+
+                let mut remaining = value;
+                let shares: Vec<_> = recipients
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (to, bps))| {
+                        let share = if i + 1 == recipients.len() {
+                            remaining
+                        } else {
+                            bps_of(value, *bps)
+                        };
+                        remaining -= share;
+                        (*to, share)
+                    })
+                    .collect();
+                // debit the sender once for all the shares
+                let from_balance = erc20.balance_of(from);
+                erc20.set_balance(from, from_balance - value);
+                erc20.credit_shares(from, from, &shares, volume_window, true);
+                Ok(())
+            })
+        }
+
+        /// getter for the batch events flag
+        #[ink(message)]
+        pub fn batch_events(&self) -> bool {
+            *self.batch_events
+        }
+
+        /// when enabled, batch transfers emit a single `BatchTransfer` event
+        /// instead of a `Transfer` event per recipient, only callable by the owner
+        #[ink(message)]
+        pub fn set_batch_events(&mut self, enabled: bool) -> Result<()> {
+            self.ensure_owner()?;
+            Lazy::set(&mut self.batch_events, enabled);
+            Ok(())
+        }
+
+        /// get the block before which `who` can't send its tokens
+        /// return 0 if its tokens aren't locked
+        #[ink(message)]
+        pub fn unlock_block_of(&self, who: AccountId) -> BlockNumber {
+            self.unlock_blocks.get(&who).copied().unwrap_or(0)
+        }
+
+        /// get the block before which the deployer can't send its tokens
+        #[ink(message)]
+        pub fn deployer_unlock_block(&self) -> BlockNumber {
+            self.unlock_block_of(*self.deployer)
+        }
+
+        /// lock caller's tokens until `unlock_block`, a lock can only be extended
+        ///
+        /// throw InvalidParameter if `unlock_block` is before the current unlock block
+        #[ink(message)]
+        pub fn lock_until(&mut self, unlock_block: BlockNumber) -> Result<()> {
+            let who = self.env().caller();
+            if unlock_block < self.unlock_block_of(who) {
+                return Err(Error::InvalidParameter);
+            }
+            self.unlock_blocks.insert(who, unlock_block);
+            Ok(())
+        }
+
+        /// remove the expired locks and fully released vesting schedules of
+        /// `accounts` to free their storage, callable by anyone
+        ///
+        /// emit `LocksSwept` event with the number of removed entries
+        #[ink(message)]
+        pub fn sweep_expired(&mut self, accounts: Vec<AccountId>) {
+            let now = self.env().block_number();
+            let mut count = 0;
+            for who in accounts {
+                if self
+                    .unlock_blocks
+                    .get(&who)
+                    .is_some_and(|unlock| *unlock <= now)
+                {
+                    self.unlock_blocks.take(&who);
+                    count += 1;
+                }
+                let released = self
+                    .vestings
+                    .get(&who)
+                    .is_some_and(|schedule| schedule.released == schedule.total);
+                if released {
+                    self.vestings.take(&who);
+                    Lazy::get_mut(&mut self.storage_stats).vesting_count -= 1;
+                    count += 1;
+                }
+            }
+            self.env().emit_event(LocksSwept { count });
+        }
+
+        /// getter for the max number of recipients of a batch transfer
+        #[ink(message)]
+        pub fn max_batch_size(&self) -> u32 {
+            *self.max_batch_size
+        }
+
+        /// set the max number of recipients of a batch transfer, only callable
+        /// by the owner
+        /// throw InvalidParameter if `size` is zero
+        #[ink(message)]
+        pub fn set_max_batch_size(&mut self, size: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if size == 0 {
+                return Err(Error::InvalidParameter);
+            }
+            Lazy::set(&mut self.max_batch_size, size);
+            Ok(())
+        }
+
+        /// transfer each `(to, value)` of `transfers` from caller's account
+        ///
+        /// the batch counts as a single transfer of the total for the sender checks
+        /// and the fee, burn and tax, its net split pro rata among the transfers
+        /// throw Reentrancy from within the external calls of another message
+        /// throw BatchTooLarge if there are more transfers than max_batch_size()
+        /// throw Overflow if the values sum up above the max balance
+        #[ink(message)]
+        pub fn batch_transfer(&mut self, transfers: Vec<(AccountId, Balance)>) -> Result<()> {
+            let from = self.env().caller();
+            self.inner_batch_transfer(from, &transfers)
+        }
+
+        /// transfer `value` tokens from caller's account to each of `recipients`
+        /// fail as batch_transfer() does
+        #[ink(message)]
+        pub fn airdrop_equal(&mut self, recipients: Vec<AccountId>, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            let transfers: Vec<_> = recipients.into_iter().map(|to| (to, value)).collect();
+            self.inner_batch_transfer(from, &transfers)
+        }
+
+        /// internal function for batch transfers
+        /// used by both batch_transfer() and airdrop_equal()
+        ///
+        /// when success, emit one `BatchTransfer` event in batch events mode,
+        /// otherwise a `Transfer` event per recipient
+        fn inner_batch_transfer(
+            &mut self,
+            from: AccountId,
+            transfers: &[(AccountId, Balance)],
+        ) -> Result<()> {
+            self.non_reentrant(|erc20| {
+                if transfers.len() > *erc20.max_batch_size as usize {
+                    return Err(Error::BatchTooLarge);
+                }
+                let total = transfers
+                    .iter()
+                    .try_fold(0, |total: Balance, (_, value)| total.checked_add(*value))
+                    .ok_or(Error::Overflow)?;
+                let volume_window = erc20.check_sender(from, total)?;
+                for (to, _) in transfers {
+                    erc20.check_recipient(from, *to)?;
+                }
+
+                let batch_events = *erc20.batch_events;
+                let from_balance = erc20.balance_of(from);
+                erc20.set_balance(from, from_balance - total);
+                erc20.credit_shares(from, from, transfers, volume_window, !batch_events);
+                if batch_events {
+                    erc20.env().emit_event(BatchTransfer {
+                        from,
+                        total,
+                        count: transfers.len() as u32,
+                    });
+                }
+                Ok(())
+            })
+        }
+
+        /// internal function for transfer
+        /// used by transfer() and the other transfers without allowance
+        ///
+        /// throw Reentrancy from within the external calls of another message
+        /// fail as check_sender() and check_recipient() do
+        /// when success, emit `Transfer` event, plus one for the fee and one for
+        /// the burned part if any, then notify the observers
+        pub fn inner_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.non_reentrant(|erc20| erc20.unguarded_transfer(from, to, value))
+        }
+
+        /// same as inner_transfer() without the reentrancy guard
+        fn unguarded_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let volume_window = self.check_sender(from, value)?;
+            self.check_recipient(from, to)?;
+            self.apply_transfer(from, to, value, volume_window);
+            Ok(())
+        }
+
+        /// run `f` holding the reentrancy guard, which every message making
+        /// external calls takes
+        /// the contract called by call_reentrant() may enter again, leaving
+        /// the guard to the message holding it
+        ///
+        /// throw Reentrancy if another message holds it
+        fn non_reentrant<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+            if *self.entered {
+                if *self.reentrant_callee == Some(self.env().caller()) {
+                    return f(self);
+                }
+                return Err(Error::Reentrancy);
+            }
+            Lazy::set(&mut self.entered, true);
+            let result = f(self);
+            Lazy::set(&mut self.entered, false);
+            result
+        }
+
+        /// make the external call `call` to `callee`, which may enter the
+        /// messages holding the reentrancy guard until it returns, as the
+        /// router moves the treasury's tokens or a spender its allowance
+        fn call_reentrant<T>(&mut self, callee: AccountId, call: impl FnOnce() -> T) -> T {
+            let previous = *self.reentrant_callee;
+            Lazy::set(&mut self.reentrant_callee, Some(callee));
+            let result = call();
+            Lazy::set(&mut self.reentrant_callee, previous);
+            result
+        }
+
+        /// getter for whether a message making external calls is running,
+        /// only ever true for the contracts it calls
+        #[ink(message)]
+        pub fn is_locked(&self) -> bool {
+            *self.entered
+        }
+
+        /// getter for whether `who` may call unchecked_transfer()
+        #[ink(message)]
+        pub fn is_trusted_caller(&self, who: AccountId) -> bool {
+            self.trusted_callers.contains_key(&who)
+        }
+
+        /// allow or disallow `who` to call unchecked_transfer(), only callable
+        /// by the owner
+        #[ink(message)]
+        pub fn set_trusted_caller(&mut self, who: AccountId, trusted: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if trusted {
+                self.trusted_callers.insert(who, ());
+            } else {
+                self.trusted_callers.take(&who);
+            }
+            Ok(())
+        }
+
+        /// DANGER: same as transfer() without the reentrancy guard, so a
+        /// vetted router saves its storage accesses
+        /// the caller may transfer again from within the external calls of
+        /// its transfer, only trust contracts which can't be abused that way
+        ///
+        /// throw NotTrusted if the caller isn't a trusted caller
+        /// fail as check_sender() and check_recipient() do
+        #[ink(message)]
+        pub fn unchecked_transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            if !self.is_trusted_caller(from) {
+                return Err(Error::NotTrusted);
+            }
+            self.unguarded_transfer(from, to, value)
+        }
+
+        /// check `from` may send `value` tokens, without changing any state
+        ///
+        /// throw Paused while paused, unless `from` is the fee recipient
+        /// and treasury ops are allowed during pause
+        /// throw Blacklisted if `from` is blacklisted
+        /// throw TokensLocked before `from`'s unlock block
+        /// throw DailyLimitExceeded if `from` would exceed its daily limit
+        /// throw NotWhitelisted in whitelist mode if `from` is neither
+        /// whitelisted nor the owner
+        /// throw MaxTxExceeded if `value` is above the max transaction amount
+        /// throw VolumeCapExceeded if the volume window is full, unless `from` is the owner
+        /// throw InsufficientBalance if not enough tokens on the `from` account
+        /// return the volume window to store once the transfer is applied
+        fn check_sender(
+            &self,
+            from: AccountId,
+            value: Balance,
+        ) -> Result<Option<(BlockNumber, Balance)>> {
+            let volume_window = self.check_sender_rules(from, value)?;
+            if self.balance_of(from) < value {
+                return Err(Error::InsufficientBalance);
+            }
+            Ok(volume_window)
+        }
+
+        /// same as check_sender() without the balance check, for tokens
+        /// `from` already escrowed
+        fn check_sender_rules(
+            &self,
+            from: AccountId,
+            value: Balance,
+        ) -> Result<Option<(BlockNumber, Balance)>> {
+            let treasury_op = *self.treasury_ops_during_pause && from == *self.fee_recipient;
+            if self.paused() && !treasury_op {
+                return Err(Error::Paused);
+            }
+
+            if self.is_blacklisted(from) {
+                return Err(Error::Blacklisted);
+            }
+
+            if self.env().block_number() < self.unlock_block_of(from) {
+                return Err(Error::TokensLocked);
+            }
+            self.check_daily_limit(from, value)?;
+
+            if *self.whitelist_mode && from != *self.owner && !self.is_whitelisted(from) {
+                return Err(Error::NotWhitelisted);
+            }
+
+            let max_tx_amount = *self.max_tx_amount;
+            if max_tx_amount != 0 && value > max_tx_amount {
+                return Err(Error::MaxTxExceeded);
+            }
+
+            let max_volume = *self.max_volume_per_window;
+            let volume_window = if max_volume != 0 && from != *self.owner {
+                let (window_start, volume) = self.current_volume_window();
+                let volume = volume
+                    .checked_add(value)
+                    .filter(|volume| *volume <= max_volume)
+                    .ok_or(Error::VolumeCapExceeded)?;
+                Some((window_start, volume))
+            } else {
+                None
+            };
+            Ok(volume_window)
+        }
+
+        /// check `to` may receive tokens from `from`, without changing any state
+        ///
+        /// throw Blacklisted if `to` is blacklisted
+        /// throw NotWhitelisted in whitelist mode if `to` is neither
+        /// whitelisted nor the owner, unless `from` is the owner
+        /// throw RecipientNotOptedIn if opt-in is required and `to` didn't opt in,
+        /// unless `from` is the owner
+        /// throw CooldownActive if `from` transferred within the cooldown blocks,
+        /// unless `to` is an AMM pair so sells are never throttled
+        fn check_recipient(&self, from: AccountId, to: AccountId) -> Result<()> {
+            if self.is_blacklisted(to) {
+                return Err(Error::Blacklisted);
+            }
+
+            let owner = *self.owner;
+            if *self.whitelist_mode && from != owner && to != owner && !self.is_whitelisted(to) {
+                return Err(Error::NotWhitelisted);
+            }
+
+            if *self.require_opt_in && from != *self.owner && !self.is_opted_in(to) {
+                return Err(Error::RecipientNotOptedIn);
+            }
+
+            let cooldown_blocks = *self.cooldown_blocks;
+            if cooldown_blocks != 0 && !self.is_amm_pair(to) {
+                if let Some(last) = self.last_transfer_block.get(&from) {
+                    if self.env().block_number() < last.saturating_add(cooldown_blocks) {
+                        return Err(Error::CooldownActive);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// move `value` tokens from `from` to `to` once the checks passed,
+        /// charging the fee and burn and storing `volume_window`
+        fn apply_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            volume_window: Option<(BlockNumber, Balance)>,
+        ) {
+            let from_balance = self.balance_of(from);
+            self.set_balance(from, from_balance - value);
+            self.credit_transfer(from, from, to, value, volume_window, true);
+        }
+
+        /// record `from` sent `value` tokens for its cooldown, transfer count,
+        /// daily limit and activity, and store `volume_window`
+        fn record_spend(
+            &mut self,
+            from: AccountId,
+            value: Balance,
+            volume_window: Option<(BlockNumber, Balance)>,
+        ) {
+            if *self.cooldown_blocks != 0 {
+                let now = self.env().block_number();
+                self.last_transfer_block.insert(from, now);
+            }
+            let transfer_count = self.transfer_count(from).saturating_add(1);
+            self.transfer_counts.insert(from, transfer_count);
+            self.record_daily_spend(from, value);
+            self.record_activity(from);
+            if let Some((window_start, volume)) = volume_window {
+                Lazy::set(&mut self.window_start, window_start);
+                Lazy::set(&mut self.volume_this_window, volume);
+            }
+        }
+
+        /// same as apply_transfer() once `from` has already been debited `value`
+        /// the events are emitted from `source`, the account holding the
+        /// tokens, `from` unless they were escrowed
+        /// the `Transfer` event to `to` is skipped unless `emit_transfer`
+        /// what a burn address would receive is burned instead
+        fn credit_transfer(
+            &mut self,
+            from: AccountId,
+            source: AccountId,
+            to: AccountId,
+            value: Balance,
+            volume_window: Option<(BlockNumber, Balance)>,
+            emit_transfer: bool,
+        ) {
+            self.credit_shares(from, source, &[(to, value)], volume_window, emit_transfer);
+        }
+
+        /// same as credit_transfer() for a transfer split into the `(to, value)`
+        /// `shares` once `from` has already been debited their total
+        /// the fee, burn, tax and reflection are charged once on the total not
+        /// sent to the fee recipient, whose net is split pro rata among the shares
+        fn credit_shares(
+            &mut self,
+            from: AccountId,
+            source: AccountId,
+            shares: &[(AccountId, Balance)],
+            volume_window: Option<(BlockNumber, Balance)>,
+            emit_transfer: bool,
+        ) {
+            let fee_recipient = *self.fee_recipient;
+            let charged = |to: &AccountId| *to != fee_recipient;
+            let value: Balance = shares.iter().map(|(_, share)| share).sum();
+            let charged_value: Balance = shares
+                .iter()
+                .filter(|(to, _)| charged(to))
+                .map(|(_, share)| share)
+                .sum();
+            let preview = self.preview_charges(from, charged_value);
+
+            let mut net_left = preview.net_to_recipient;
+            let mut charged_left = charged_value;
+            for (to, share) in shares {
+                let received = if charged(to) {
+                    charged_left -= share;
+                    // the last charged share gets the rounding leftover
+                    let received = match charged_left {
+                        0 => net_left,
+                        _ => mul_div(*share, net_left, charged_left + share),
+                    };
+                    net_left -= received;
+                    received
+                } else {
+                    *share
+                };
+                self.credit_share(source, *to, received, emit_transfer);
+            }
+            let to_fee_recipient = preview.fee + preview.tax;
+            if to_fee_recipient > 0 {
+                let fee_recipient_balance = self.balance_of(fee_recipient);
+                self.set_balance(fee_recipient, fee_recipient_balance + to_fee_recipient);
+                self.env().emit_event(Transfer {
+                    from: Some(source),
+                    to: Some(fee_recipient),
+                    value: to_fee_recipient,
+                    namespace: *self.topic_namespace,
+                });
+            }
+            if preview.burn > 0 {
+                let total_supply = *self.total_supply - preview.burn;
+                self.set_total_supply(total_supply);
+                self.env().emit_event(Transfer {
+                    from: Some(source),
+                    to: None,
+                    value: preview.burn,
+                    namespace: *self.topic_namespace,
+                });
+                self.track_buyback(preview.burn);
+            }
+            if preview.reflection > 0 {
+                // the reflected balances are now worth the reflection more
+                let reflected_tokens = *self.reflected_tokens + preview.reflection;
+                Lazy::set(&mut self.reflected_tokens, reflected_tokens);
+                self.env().emit_event(Reflected {
+                    from: source,
+                    value: preview.reflection,
+                });
+            }
+            self.record_spend(from, value, volume_window);
+            if *self.clear_allowances_on_empty && self.balance_of(from) == 0 {
+                self.clear_allowances(from);
+            }
+            self.swap_fees(from);
+            // observers are told of each recipient
+            for (to, share) in shares {
+                self.notify_observers(from, *to, *share);
+            }
+        }
+
+        /// credit `to` the `received` net of a transfer held by `source`,
+        /// burning it instead if `to` is a burn address
+        /// the `Transfer` event to `to` is skipped unless `emit_transfer`
+        fn credit_share(
+            &mut self,
+            source: AccountId,
+            to: AccountId,
+            received: Balance,
+            emit_transfer: bool,
+        ) {
+            if self.is_burn_address(to) {
+                let total_supply = *self.total_supply - received;
+                self.set_total_supply(total_supply);
+                self.env().emit_event(Transfer {
+                    from: Some(source),
+                    to: None,
+                    value: received,
+                    namespace: *self.topic_namespace,
+                });
+                self.track_buyback(received);
+            } else {
+                let to_balance = self.balance_of(to);
+                self.set_balance(to, to_balance + received);
+                if emit_transfer {
+                    self.env().emit_event(Transfer {
+                        from: Some(source),
+                        to: Some(to),
+                        value: received,
+                        namespace: *self.topic_namespace,
+                    });
+                }
+            }
+        }
+    }
+
+    /// selector of the buyback contract's `trigger()` message
+    const TRIGGER_SELECTOR: [u8; 4] = [0x51, 0x42, 0x8a, 0x39];
+
+    /// selector of the observer contracts' `notify_transfer(from, to, value)` message
+    const NOTIFY_TRANSFER_SELECTOR: [u8; 4] = [0xd3, 0xc7, 0x19, 0x74];
+
+    /// selector of the spender contracts' `receive_approval(owner, value, data)` message
+    const RECEIVE_APPROVAL_SELECTOR: [u8; 4] = [0x81, 0xc8, 0x67, 0xf1];
+
+    /// selector of the vault contract's `redeem(who, amount)` message
+    const REDEEM_SELECTOR: [u8; 4] = [0xec, 0x3e, 0x92, 0x90];
+
+    /// selector of the backing token's `balance_of(who)` message
+    const BALANCE_OF_SELECTOR: [u8; 4] = [0x0f, 0x75, 0x5a, 0x56];
+
+    /// selector of the backing token's `transfer(to, value)` message
+    const TRANSFER_SELECTOR: [u8; 4] = [0x84, 0xa1, 0x5d, 0xa1];
+
+    /// selector of the wrapped token's `transfer_from(from, to, value)` message
+    const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x0b, 0x39, 0x6f, 0x18];
+
+    /// selector of the router contract's `swap_tokens_for_native(amount)` message
+    const SWAP_TOKENS_FOR_NATIVE_SELECTOR: [u8; 4] = [0x9e, 0xd1, 0xd6, 0xa2];
+
+    /// selector of the new token contract's `receive_migration(entries)` message
+    const RECEIVE_MIGRATION_SELECTOR: [u8; 4] = [0x68, 0x7a, 0x37, 0x52];
+
+    /// max number of registered transfer observers
+    const MAX_OBSERVERS: usize = 8;
+
+    /// number of blocks of a day at 6 seconds per block
+    const DAY_BLOCKS: BlockNumber = 14_400;
+
+    /// max number of distinct spenders of an account until the owner changes it
+    const DEFAULT_MAX_SPENDERS_PER_OWNER: u32 = 64;
+
+    /// max number of fee brackets
+    const MAX_FEE_BRACKETS: usize = 8;
+
+    /// max number of scheduled fee holidays
+    const MAX_FEE_HOLIDAYS: usize = 16;
+
+    /// max number of recipients of a batch transfer until the owner changes it
+    const DEFAULT_MAX_BATCH_SIZE: u32 = 100;
+
+    /// min number of blocks a parameter change waits before it can be
+    /// applied, an hour at 6 seconds per block
+    const MIN_PARAMETER_DELAY_BLOCKS: BlockNumber = 600;
+
+    /// max number of blocks a parameter change waits before it can be
+    /// applied, so the governor can't freeze the parameters
+    const MAX_PARAMETER_DELAY_BLOCKS: BlockNumber = 7 * DAY_BLOCKS;
+
+    /// compute the Merkle leaf of `amount` tokens airdropped to `who`
+    fn merkle_leaf(who: AccountId, amount: Balance) -> [u8; 32] {
+        let mut output = [0; 32];
+        ink_env::hash_encoded::<Blake2x256, _>(&(who, amount), &mut output);
+        output
+    }
+
+    /// compute the parent of two Merkle nodes, sorted so proofs don't need
+    /// to tell left from right siblings
+    fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let mut input = [0; 64];
+        input[..32].copy_from_slice(first);
+        input[32..].copy_from_slice(second);
+        let mut output = [0; 32];
+        ink_env::hash_bytes::<Blake2x256>(&input, &mut output);
+        output
+    }
+
+    /// number of decimals of the token unless constructed with others
+    const DEFAULT_DECIMALS: u8 = 18;
+
+    /// max number of decimals whose unit fits a balance
+    const MAX_DECIMALS: u8 = 38;
+
+    /// format `value` base units of a token with `decimals` decimals
+    /// in whole tokens, keeping `display_decimals` fractional digits
+    fn format_amount(value: Balance, decimals: u8, display_decimals: u8) -> String {
+        let unit = 10u128.pow(u32::from(decimals));
+        let whole = value / unit;
+        if display_decimals == 0 {
+            return format!("{}", whole);
+        }
+        let fraction = format!("{:0width$}", value % unit, width = usize::from(decimals));
+        format!("{}.{}", whole, &fraction[..usize::from(display_decimals)])
+    }
+
+    /// convert `value` base units of a token with `from` decimals to the
+    /// base units of a token with `to` decimals, rounding down
+    /// return None if it doesn't fit in a `Balance`
+    fn scale_decimals(value: Balance, from: u8, to: u8) -> Option<Balance> {
+        if to >= from {
+            10u128
+                .checked_pow(u32::from(to - from))
+                .and_then(|unit| value.checked_mul(unit))
+        } else {
+            Some(value / 10u128.pow(u32::from(from - to)))
+        }
+    }
+
+    /// compute the share of `holdings` backing `value` tokens out of
+    /// `total_supply`, rounding down, 0 without supply
+    /// return None if the product overflows
+    fn redeemed_share(holdings: Balance, value: Balance, total_supply: Balance) -> Option<Balance> {
+        match total_supply {
+            0 => Some(0),
+            _ => holdings
+                .checked_mul(value)
+                .map(|product| product / total_supply),
+        }
+    }
+
+    /// max number of entries kept in the supply history
+    const MAX_SUPPLY_HISTORY: usize = 64;
+
+    /// 100% expressed in basis points
+    const MAX_BPS: u128 = 10_000;
+
+    /// compute `bps` basis points of `value` without overflowing
+    fn bps_of(value: Balance, bps: u16) -> Balance {
+        let bps = Balance::from(bps);
+        value / MAX_BPS * bps + value % MAX_BPS * bps / MAX_BPS
+    }
+
+    /// update the entry count of a map after writing one of its keys
+    fn count_entry(count: u32, existed: bool, exists: bool) -> u32 {
+        match (existed, exists) {
+            (false, true) => count + 1,
+            (true, false) => count - 1,
+            _ => count,
+        }
+    }
+
+    /// compute `a * b / c` rounded down without overflowing the product,
+    /// the result itself must fit in a `Balance`
+    fn mul_div(a: Balance, b: Balance, c: Balance) -> Balance {
+        let quotient = a / c * b;
+        let a = a % c;
+
+        // long multiplication over the bits of `b`, keeping
+        // the partial product as `q * c + r` with `r < c`
+        let (mut q, mut r) = (0, 0);
+        let add = |x: Balance, y: Balance, q: &mut Balance| {
+            if x >= c - y {
+                *q += 1;
+                x - (c - y)
+            } else {
+                x + y
+            }
+        };
+        for bit in (0..Balance::BITS - b.leading_zeros()).rev() {
+            q *= 2;
+            r = add(r, r, &mut q);
+            if b >> bit & 1 == 1 {
+                r = add(r, a, &mut q);
+            }
+        }
+        quotient + q
+    }
+
+    /// same as bps_of() but rounded up
+    fn bps_of_rounded_up(value: Balance, bps: u16) -> Balance {
+        let remainder = value % MAX_BPS * Balance::from(bps) % MAX_BPS;
+        bps_of(value, bps) + Balance::from(remainder != 0)
+    }
+
+    /// unit tests
     #[cfg(test)]
     mod tests {
         use super::*;
 
-        use ink_lang as ink;
+        use ink_lang as ink;
+
+        type Event = <Erc20 as ::ink_lang::BaseEvent>::Type;
+
+        #[ink::test]
+        fn new_works() {
+            // Constructor works.
+            let _erc20 = Erc20::new(100);
+
+            // Transfer event triggered during initial construction.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(1, emitted_events.len());
+
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+        }
+
+        /// The total supply was applied.
+        #[ink::test]
+        fn total_supply_works() {
+            // Constructor works.
+            let erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            // Get the token total supply.
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        /// Get the actual balance of an account.
+        #[ink::test]
+        fn balance_of_works() {
+            // Constructor works
+            let erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            // Alice owns all the tokens on deployment
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            // Bob does not owns tokens
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            // Alice transfers 10 tokens to Bob.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            // Bob owns 10 tokens.
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            // Check first transfer event related to ERC-20 instantiation.
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            // Check the second transfer event relating to the actual trasfer.
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x02; 32])),
+                10,
+            );
+        }
+
+        #[ink::test]
+        fn transfer_fails_with_insufficient_balance() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or_else(|_| [0x0; 32].into());
+            // Create call
+            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+
+            // Bob fails to transfers 10 tokens to Eve, note the caller is now bob
+            assert_eq!(
+                erc20.transfer(accounts.eve, 10),
+                Err(Error::InsufficientBalance)
+            );
+            // Alice owns all the tokens.
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+
+            // Transfer event triggered during initial construction.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 1);
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_works() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // Bob fails to transfer tokens owned by Alice.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 10),
+                Err(Error::InsufficientApproval)
+            );
+            // Alice approves Bob for token transfers on her behalf.
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+
+            // The approve event takes place.
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or_else(|_| [0x0; 32].into());
+            // Create call.
+            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller.
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+
+            // Bob transfers tokens from Alice to Eve.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 10),
+                Ok(())
+            );
+            // Eve owns tokens.
+            assert_eq!(erc20.balance_of(accounts.eve), 10);
+
+            // Check all transfer events that happened during the previous calls:
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            // The second event `emitted_events[1]` is an Approve event that we skip checking.
+            assert_transfer_event(
+                &emitted_events[2],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x05; 32])),
+                10,
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_fails_with_insufficient_allowance() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // Alice approves Bob for maximal 10 tokens transfer
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+
+            // The approve event takes place.
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or_else(|_| [0x0; 32].into());
+            // Create call.
+            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller.
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+
+            // Bob tries to transfer more than he is allowed (20 > 10)
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 20),
+                Err(Error::InsufficientApproval)
+            );
+
+            // Alice's balance is unchanged
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+
+            // Eve doesn't have any tokens
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn allowance_must_not_change_on_failed_transfer() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // Alice approves Bob for token transfers on her behalf.
+            let alice_balance = erc20.balance_of(accounts.alice);
+            let initial_allowance = alice_balance + 2;
+            assert_eq!(erc20.approve(accounts.bob, initial_allowance), Ok(()));
+
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or_else(|_| [0x0; 32].into());
+            // Create call.
+            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller.
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+
+            // Bob tries to transfer tokens from Alice to Eve.
+            let emitted_events_before = ink_env::test::recorded_events();
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, alice_balance + 1),
+                Err(Error::InsufficientBalance)
+            );
+            // Allowance must have stayed the same
+            assert_eq!(
+                erc20.allowance(accounts.alice, accounts.bob),
+                initial_allowance
+            );
+            // No more events must have been emitted
+            let emitted_events_after = ink_env::test::recorded_events();
+            assert_eq!(emitted_events_before.count(), emitted_events_after.count());
+        }
+
+        #[ink::test]
+        fn set_parameter_only_by_governor() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new_with_governor(100, accounts.bob);
+            assert_eq!(erc20.governor(), accounts.bob);
+
+            // Alice deployed the contract but is not the governor
+            assert_eq!(
+                erc20.set_parameter(ParamKey::FeeBps, 100),
+                Err(Error::NotGovernor)
+            );
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 0);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_parameter(ParamKey::FeeBps, 100), Ok(()));
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 0);
+            advance_blocks(MIN_PARAMETER_DELAY_BLOCKS);
+            assert_eq!(erc20.apply_parameter(ParamKey::FeeBps), Ok(()));
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 100);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("error decoding event data");
+            if let Event::ParameterQueued(ParameterQueued {
+                key,
+                value,
+                effective_block,
+            }) = decoded_event
+            {
+                assert_eq!(key, ParamKey::FeeBps);
+                assert_eq!(value, 100);
+                assert_eq!(effective_block, MIN_PARAMETER_DELAY_BLOCKS);
+            } else {
+                panic!("invalid event type: expected a ParameterQueued event")
+            }
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("error decoding event data");
+            if let Event::ParameterChanged(ParameterChanged { key, old, new }) = decoded_event {
+                assert_eq!(key, ParamKey::FeeBps);
+                assert_eq!(old, 0);
+                assert_eq!(new, 100);
+            } else {
+                panic!("invalid event type: expected a ParameterChanged event")
+            }
+        }
+
+        #[ink::test]
+        fn set_parameter_rejects_out_of_range_values() {
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(
+                erc20.set_parameter(ParamKey::FeeBps, 10_001),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.set_parameter(ParamKey::CooldownBlocks, u128::from(u32::MAX) + 1),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 0);
+            assert_eq!(erc20.parameter(ParamKey::CooldownBlocks), 0);
+        }
+
+        #[ink::test]
+        fn fee_bps_parameter_charges_fee() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 1_000);
+
+            // Alice is the fee recipient, her transfers are not charged
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+
+            // Bob pays 10% of the transferred value to Alice
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.charlie), 45);
+            assert_eq!(erc20.balance_of(accounts.alice), 55);
+            assert_eq!(erc20.total_supply(), 100);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 6);
+            assert_transfer_event(
+                &emitted_events[4],
+                Some(accounts.bob),
+                Some(accounts.charlie),
+                45,
+            );
+            assert_transfer_event(
+                &emitted_events[5],
+                Some(accounts.bob),
+                Some(accounts.alice),
+                5,
+            );
+        }
+
+        #[ink::test]
+        fn max_tx_amount_parameter_limits_transfers() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            set_parameter_now(&mut erc20, ParamKey::MaxTxAmount, 20);
+            assert_eq!(erc20.parameter(ParamKey::MaxTxAmount), 20);
+
+            assert_eq!(erc20.transfer(accounts.bob, 21), Err(Error::MaxTxExceeded));
+            assert_eq!(erc20.transfer(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn cooldown_blocks_parameter_throttles_senders() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            set_parameter_now(&mut erc20, ParamKey::CooldownBlocks, 2);
+            assert_eq!(erc20.parameter(ParamKey::CooldownBlocks), 2);
+
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::CooldownActive));
+            advance_blocks(1);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::CooldownActive));
+            advance_blocks(1);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn claim_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // Alice escrows 30 tokens for Bob
+            assert_eq!(erc20.create_claim(accounts.bob, 30), Ok(0));
+            assert_eq!(erc20.get_claim(0), Some((accounts.alice, accounts.bob, 30)));
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+
+            // Eve is not the recipient
+            set_caller(accounts.eve);
+            assert_eq!(erc20.claim(0), Err(Error::NotClaimRecipient));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.claim(0), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+            assert_eq!(erc20.get_claim(0), None);
+            assert_eq!(erc20.claim(0), Err(Error::ClaimNotFound));
+
+            let contract = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get contract account");
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(&emitted_events[1], Some(accounts.alice), Some(contract), 30);
+            assert_transfer_event(&emitted_events[2], Some(contract), Some(accounts.bob), 30);
+        }
+
+        #[ink::test]
+        fn claims_respect_the_transfer_checks() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.create_claim(accounts.charlie, 10), Ok(0));
+
+            // the sender's checks run when the tokens are escrowed
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.bob, BlockNumber::MAX),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.create_claim(accounts.charlie, 10),
+                Err(Error::Blacklisted)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.pause(), Ok(()));
+            assert_eq!(erc20.create_claim(accounts.charlie, 10), Err(Error::Paused));
+            assert_eq!(erc20.unpause(), Ok(()));
+
+            // the recipient's checks run when the tokens are claimed
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.charlie, BlockNumber::MAX),
+                Ok(())
+            );
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.claim(0), Err(Error::Blacklisted));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+            assert_eq!(
+                erc20.get_claim(0),
+                Some((accounts.alice, accounts.charlie, 10))
+            );
+        }
+
+        #[ink::test]
+        fn reclaim_before_claim_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.create_claim(accounts.bob, 30), Ok(0));
+            assert_eq!(
+                erc20.create_claim(accounts.bob, 200),
+                Err(Error::InsufficientBalance)
+            );
+
+            // Bob can't take back Alice's escrow
+            set_caller(accounts.bob);
+            assert_eq!(erc20.reclaim(0), Err(Error::NotClaimSender));
+
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.reclaim(0), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+
+            // the claim is settled, Bob can't claim it anymore
+            set_caller(accounts.bob);
+            assert_eq!(erc20.claim(0), Err(Error::ClaimNotFound));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn reclaim_after_claim_fails() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.create_claim(accounts.bob, 30), Ok(0));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.claim(0), Ok(()));
+
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.reclaim(0), Err(Error::ClaimNotFound));
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+        }
+
+        #[ink::test]
+        fn approve_with_label_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            let label = *b"dex router v2\0\0\0";
+            assert_eq!(erc20.approve_with_label(accounts.bob, 10, label), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("error decoding event data");
+            if let Event::Approval(Approval {
+                owner,
+                spender,
+                value,
+            }) = decoded_event
+            {
+                assert_eq!(owner, accounts.alice);
+                assert_eq!(spender, accounts.bob);
+                assert_eq!(value, 10);
+            } else {
+                panic!("invalid event type: expected an Approval event")
+            }
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("error decoding event data");
+            if let Event::SpenderLabeled(SpenderLabeled {
+                owner,
+                spender,
+                label: emitted_label,
+            }) = decoded_event
+            {
+                assert_eq!(owner, accounts.alice);
+                assert_eq!(spender, accounts.bob);
+                assert_eq!(emitted_label, label);
+            } else {
+                panic!("invalid event type: expected a SpenderLabeled event")
+            }
+        }
+
+        #[ink::test]
+        fn mint_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.owner(), accounts.alice);
+            assert_eq!(erc20.cap(), Balance::MAX);
+
+            assert_eq!(erc20.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+            assert_eq!(erc20.total_supply(), 150);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(&emitted_events[1], None, Some(accounts.bob), 50);
+
+            // only the owner mints
+            set_caller(accounts.bob);
+            assert_eq!(erc20.mint(accounts.bob, 50), Err(Error::NotOwner));
+            assert_eq!(erc20.total_supply(), 150);
+        }
+
+        #[ink::test]
+        fn cap_decays_every_window() {
+            let erc20 = Erc20::new_with_cap(100, 1_000, 200, 10);
+            assert_eq!(erc20.cap(), 1_000);
+
+            advance_blocks(9);
+            assert_eq!(erc20.cap(), 1_000);
+            advance_blocks(1);
+            assert_eq!(erc20.cap(), 800);
+            advance_blocks(20);
+            assert_eq!(erc20.cap(), 400);
+
+            // the cap never drops below the total supply
+            advance_blocks(100);
+            assert_eq!(erc20.cap(), 100);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn mint_respects_decayed_cap() {
+            let mut erc20 = Erc20::new_with_cap(100, 1_000, 200, 10);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.mint(accounts.bob, 901), Err(Error::CapExceeded));
+            assert_eq!(erc20.mint(accounts.bob, 500), Ok(()));
+
+            // two windows later the cap is 600, nothing more is mintable
+            advance_blocks(20);
+            assert_eq!(erc20.cap(), 600);
+            assert_eq!(erc20.mint(accounts.bob, 1), Err(Error::CapExceeded));
+            assert_eq!(erc20.mint(accounts.bob, 0), Ok(()));
+
+            // the cap is floored at the total supply afterwards
+            advance_blocks(10);
+            assert_eq!(erc20.cap(), 600);
+            assert_eq!(erc20.mint(accounts.bob, 1), Err(Error::CapExceeded));
+            assert_eq!(erc20.balance_of(accounts.bob), 500);
+            assert_eq!(erc20.total_supply(), 600);
+        }
+
+        #[ink::test]
+        fn stake_and_unstake_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.stake(40), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.staked_balance_of(accounts.alice), 40);
+            assert_eq!(erc20.total_staked(), 40);
+            assert_eq!(erc20.stake(61), Err(Error::InsufficientBalance));
+
+            assert_eq!(erc20.unstake(41), Err(Error::InsufficientStake));
+            assert_eq!(erc20.unstake(15), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 75);
+            assert_eq!(erc20.staked_balance_of(accounts.alice), 25);
+            assert_eq!(erc20.total_staked(), 25);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn transfer_and_stake_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // 25% of the 40 tokens are staked for Bob
+            assert_eq!(erc20.transfer_and_stake(accounts.bob, 40, 2_500), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+            assert_eq!(erc20.staked_balance_of(accounts.bob), 10);
+            assert_eq!(erc20.staked_balance_of(accounts.alice), 0);
+
+            // Bob's staked position grows with the next one
+            assert_eq!(erc20.transfer_and_stake(accounts.bob, 20, 10_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+            assert_eq!(erc20.staked_balance_of(accounts.bob), 30);
+            assert_eq!(erc20.total_staked(), 30);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(accounts.alice),
+                Some(accounts.bob),
+                40,
+            );
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("error decoding event data");
+            if let Event::Staked(Staked { who, amount }) = decoded_event {
+                assert_eq!(who, accounts.bob);
+                assert_eq!(amount, 10);
+            } else {
+                panic!("invalid event type: expected a Staked event")
+            }
+        }
+
+        #[ink::test]
+        fn transfer_and_stake_fails_without_changes() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.bob, 40, 10_001),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.bob, 101, 5_000),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.staked_balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_and_stake_respects_the_transfer_checks_and_fees() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::MaxTxAmount, 20);
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+
+            // staking all of it doesn't bypass the max transaction amount
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.charlie, 30, 10_000),
+                Err(Error::MaxTxExceeded)
+            );
+
+            // the fee is taken on the full value, half of the rest is staked
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.charlie, 20, 5_000),
+                Ok(())
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+            assert_eq!(erc20.balance_of(accounts.alice), 52);
+            assert_eq!(erc20.balance_of(accounts.charlie), 9);
+            assert_eq!(erc20.staked_balance_of(accounts.charlie), 9);
+
+            // blacklisted accounts can't stake nor unstake
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.charlie, BlockNumber::MAX),
+                Ok(())
+            );
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.stake(5), Err(Error::Blacklisted));
+            assert_eq!(erc20.unstake(5), Err(Error::Blacklisted));
+            ink_env::test::pop_execution_context();
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.charlie, 10, 5_000),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(erc20.staked_balance_of(accounts.charlie), 9);
+        }
+
+        #[ink::test]
+        fn release_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.create_vesting(accounts.bob, 40, 10, 20), Ok(()));
+            assert_eq!(
+                erc20.create_vesting(accounts.bob, 40, 10, 20),
+                Err(Error::VestingExists)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.vested_amount(accounts.bob), 0);
+
+            // a quarter of the duration elapsed
+            advance_blocks(15);
+            assert_eq!(erc20.vested_amount(accounts.bob), 10);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.releasable_amount(accounts.bob), 0);
+
+            advance_blocks(100);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 40);
+            assert_eq!(erc20.vesting_of(accounts.bob).map(|s| s.released), Some(40));
+
+            // Eve has nothing to release
+            set_caller(accounts.eve);
+            assert_eq!(erc20.release(), Err(Error::NoVesting));
+        }
+
+        #[ink::test]
+        fn release_to_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.create_vesting(accounts.bob, 40, 0, 20), Ok(()));
+            advance_blocks(10);
+
+            // Bob releases his vested tokens to Charlie
+            set_caller(accounts.bob);
+            assert_eq!(erc20.release_to(accounts.charlie), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.releasable_amount(accounts.bob), 0);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("error decoding event data");
+            if let Event::VestingReleased(VestingReleased {
+                beneficiary,
+                recipient,
+                amount,
+            }) = decoded_event
+            {
+                assert_eq!(beneficiary, accounts.bob);
+                assert_eq!(recipient, accounts.charlie);
+                assert_eq!(amount, 20);
+            } else {
+                panic!("invalid event type: expected a VestingReleased event")
+            }
+
+            // only the beneficiary releases its own schedule
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.release_to(accounts.charlie), Err(Error::NoVesting));
+        }
+
+        #[ink::test]
+        fn pull_payment_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // Alice subscribes to Bob's service for 10 tokens every 5 blocks
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_subscription(accounts.bob, 10, 5), Ok(()));
+
+            advance_blocks(5);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.pull_payment(accounts.alice), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+            assert_eq!(
+                erc20
+                    .subscription(accounts.alice, accounts.bob)
+                    .map(|s| s.last_pull_block),
+                Some(5)
+            );
+
+            // two more intervals are pulled at once
+            advance_blocks(11);
+            assert_eq!(erc20.pull_payment(accounts.alice), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn pull_payment_fails_too_early() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.set_subscription(accounts.bob, 10, 0),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_subscription(accounts.bob, 10, 5), Ok(()));
+
+            advance_blocks(4);
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.pull_payment(accounts.alice),
+                Err(Error::IntervalNotElapsed)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+
+            // Eve has no subscription from Alice
+            set_caller(accounts.eve);
+            assert_eq!(
+                erc20.pull_payment(accounts.alice),
+                Err(Error::NoSubscription)
+            );
+        }
+
+        #[ink::test]
+        fn pull_payment_is_bounded_by_allowance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.approve(accounts.bob, 5), Ok(()));
+            assert_eq!(erc20.set_subscription(accounts.bob, 10, 5), Ok(()));
+
+            advance_blocks(5);
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.pull_payment(accounts.alice),
+                Err(Error::InsufficientApproval)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(
+                erc20
+                    .subscription(accounts.alice, accounts.bob)
+                    .map(|s| s.last_pull_block),
+                Some(0)
+            );
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.burn(101), Err(Error::InsufficientBalance));
+            assert_eq!(erc20.burn(30), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.total_supply(), 70);
+            assert_eq!(erc20.cap(), Balance::MAX);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            assert_transfer_event(&emitted_events[1], Some(accounts.alice), None, 30);
+        }
+
+        #[ink::test]
+        fn burn_and_lower_cap_works() {
+            let mut erc20 = Erc20::new_with_cap(100, 1_000, 0, 0);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.burn_and_lower_cap(30), Ok(()));
+            assert_eq!(erc20.total_supply(), 70);
+            assert_eq!(erc20.cap(), 970);
+
+            // the burned tokens can't be minted again
+            assert_eq!(erc20.mint(accounts.bob, 930), Err(Error::CapExceeded));
+            assert_eq!(erc20.mint(accounts.bob, 900), Ok(()));
+            assert_eq!(erc20.total_supply(), 970);
+        }
+
+        #[ink::test]
+        fn burn_and_lower_cap_fails_without_changes() {
+            let mut erc20 = Erc20::new_with_cap(100, 1_000, 0, 0);
+            assert_eq!(
+                erc20.burn_and_lower_cap(101),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.cap(), 1_000);
+        }
+
+        #[ink::test]
+        fn new_with_allocations_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let erc20 = Erc20::new_with_allocations(vec![
+                (accounts.bob, 10),
+                (accounts.charlie, 20),
+                (accounts.django, 30),
+            ]);
+
+            assert_eq!(erc20.total_supply(), 60);
+            assert_eq!(erc20.balance_of(accounts.alice), 0);
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.balance_of(accounts.django), 30);
+
+            // one Transfer event per allocation
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(&emitted_events[0], None, Some(accounts.bob), 10);
+            assert_transfer_event(&emitted_events[1], None, Some(accounts.charlie), 20);
+            assert_transfer_event(&emitted_events[2], None, Some(accounts.django), 30);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "DuplicateRecipient")]
+        fn new_with_allocations_rejects_duplicates() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            Erc20::new_with_allocations(vec![
+                (accounts.bob, 10),
+                (accounts.charlie, 20),
+                (accounts.bob, 30),
+            ]);
+        }
+
+        #[ink::test]
+        fn validate_allocations_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                Erc20::validate_allocations(&[(accounts.bob, 10), (accounts.charlie, 20)]),
+                Ok(30)
+            );
+            assert_eq!(
+                Erc20::validate_allocations(&[(accounts.bob, 10), (accounts.bob, 20)]),
+                Err(Error::DuplicateRecipient)
+            );
+            assert_eq!(
+                Erc20::validate_allocations(&[(accounts.bob, Balance::MAX), (accounts.charlie, 1)]),
+                Err(Error::Overflow)
+            );
+        }
+
+        #[ink::test]
+        fn burn_counts_towards_buyback() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.set_buyback(Some(accounts.django), 50),
+                Err(Error::NotOwner)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.set_buyback(Some(accounts.django), 50), Ok(()));
+            assert_eq!(erc20.buyback(), (Some(accounts.django), 50));
+
+            // below the threshold the buyback contract isn't called
+            assert_eq!(erc20.burn(20), Ok(()));
+            assert_eq!(erc20.burn(29), Ok(()));
+            assert_eq!(erc20.burned_since_buyback(), 49);
+        }
+
+        #[ink::test]
+        fn burn_crossing_threshold_calls_buyback() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_buyback(Some(accounts.django), 50), Ok(()));
+            assert_eq!(erc20.burn(49), Ok(()));
+
+            // the burn is done and counted by the time `trigger()` is called
+            assert_reaches_contract_call(|| {
+                let _ = erc20.burn(1);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 50);
+            assert_eq!(erc20.total_supply(), 50);
+            assert_eq!(erc20.burned_since_buyback(), 50);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(&emitted_events[2], Some(accounts.alice), None, 1);
+        }
+
+        #[ink::test]
+        fn burn_without_buyback_contract_only_counts() {
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.set_buyback(None, 50), Ok(()));
+            assert_eq!(erc20.burn(60), Ok(()));
+            assert_eq!(erc20.burned_since_buyback(), 60);
+            assert_eq!(erc20.total_supply(), 40);
+        }
+
+        #[ink::test]
+        fn volume_cap_limits_transfers_per_window() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_volume_cap(30, 10), Ok(()));
+            assert_eq!(erc20.volume_cap(), (30, 10));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_volume_cap(0, 0), Err(Error::NotOwner));
+            assert_eq!(erc20.transfer(accounts.charlie, 20), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.volume_this_window(), 30);
+
+            // the window is full
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 1),
+                Err(Error::VolumeCapExceeded)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 20);
+
+            // the next window starts empty
+            advance_blocks(10);
+            assert_eq!(erc20.volume_this_window(), 0);
+            assert_eq!(erc20.transfer(accounts.charlie, 20), Ok(()));
+            assert_eq!(erc20.volume_this_window(), 20);
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+        }
+
+        #[ink::test]
+        fn volume_cap_exempts_owner() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_volume_cap(0, 10), Ok(()));
+            assert_eq!(erc20.set_volume_cap(30, 0), Err(Error::InvalidParameter));
+            assert_eq!(erc20.set_volume_cap(30, 10), Ok(()));
+
+            assert_eq!(erc20.transfer(accounts.bob, 80), Ok(()));
+            assert_eq!(erc20.volume_this_window(), 0);
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 31),
+                Err(Error::VolumeCapExceeded)
+            );
+        }
+
+        #[ink::test]
+        fn preview_transfer_matches_transfer() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 500), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 300);
+            set_parameter_now(&mut erc20, ParamKey::BurnBps, 200);
+            assert_eq!(erc20.parameter(ParamKey::BurnBps), 200);
+
+            let preview = erc20.preview_transfer(accounts.bob, accounts.charlie, 200);
+            assert_eq!(
+                preview,
+                TransferPreview {
+                    net_to_recipient: 190,
+                    fee: 6,
+                    burn: 4,
+                    tax: 0,
+                    reflection: 0,
+                }
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 200), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 300);
+            assert_eq!(erc20.balance_of(accounts.charlie), preview.net_to_recipient);
+            assert_eq!(erc20.balance_of(accounts.alice), 500 + preview.fee);
+            assert_eq!(erc20.total_supply(), 1_000 - preview.burn);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 9);
+            assert_transfer_event(&emitted_events[8], Some(accounts.bob), None, 4);
+        }
+
+        #[ink::test]
+        fn preview_transfer_exempts_fee_recipient() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 300);
+            set_parameter_now(&mut erc20, ParamKey::BurnBps, 200);
+
+            assert_eq!(
+                erc20.preview_transfer(accounts.alice, accounts.bob, 100),
+                TransferPreview {
+                    net_to_recipient: 100,
+                    ..Default::default()
+                }
+            );
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+            assert_eq!(erc20.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn whitelist_mode_blocks_other_accounts() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_whitelist_mode(true), Ok(()));
+            assert!(erc20.whitelist_mode());
+
+            // neither Bob nor Charlie are whitelisted
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::NotWhitelisted)
+            );
+            assert_eq!(erc20.add_to_whitelist(accounts.bob), Err(Error::NotOwner));
+
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.add_to_whitelist(accounts.bob), Ok(()));
+            assert!(erc20.is_whitelisted(accounts.bob));
+
+            // Charlie still isn't whitelisted, but the owner is
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::NotWhitelisted)
+            );
+            assert_eq!(erc20.transfer(accounts.alice, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 40);
+        }
+
+        #[ink::test]
+        fn whitelist_mode_allows_whitelisted_pairs() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.add_to_whitelist(accounts.bob), Ok(()));
+            assert_eq!(erc20.add_to_whitelist(accounts.charlie), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_whitelist_mode(true), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+
+            // removed accounts can't receive anymore
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.remove_from_whitelist(accounts.charlie), Ok(()));
+            assert!(!erc20.is_whitelisted(accounts.charlie));
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::NotWhitelisted)
+            );
+
+            // no restriction once the whitelist mode is off
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.set_whitelist_mode(false), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn min_fee_applies_to_small_transfers() {
+            let mut erc20 = Erc20::new(100_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50_000), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::MinFee, 5);
+            assert_eq!(erc20.parameter(ParamKey::MinFee), 5);
+
+            // no percentage fee, no min fee
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+
+            ink_env::test::pop_execution_context();
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 100);
+
+            // 1% of 100 rounds to 1, the min fee is charged instead
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 105);
+            assert_eq!(erc20.balance_of(accounts.alice), 50_005);
+
+            // large transfers pay the percentage fee
+            assert_eq!(erc20.transfer(accounts.charlie, 10_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10_005);
+            assert_eq!(erc20.balance_of(accounts.alice), 50_105);
+
+            // the fee never exceeds the transferred value
+            assert_eq!(
+                erc20
+                    .preview_transfer(accounts.bob, accounts.charlie, 3)
+                    .fee,
+                3
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_returning_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from_returning(accounts.alice, accounts.eve, 30),
+                Ok(70)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 70);
+            assert_eq!(erc20.balance_of(accounts.eve), 30);
+            assert_eq!(
+                erc20.transfer_from_returning(accounts.alice, accounts.eve, 71),
+                Err(Error::InsufficientApproval)
+            );
+        }
+
+        #[ink::test]
+        fn unlimited_allowance_is_not_consumed() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, Balance::MAX), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from_returning(accounts.alice, accounts.eve, 30),
+                Ok(Balance::MAX)
+            );
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 30),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), Balance::MAX);
+            assert_eq!(erc20.balance_of(accounts.eve), 60);
+        }
+
+        #[ink::test]
+        fn blacklist_expires() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.add_to_blacklist(accounts.bob, 5), Ok(()));
+            assert!(erc20.is_blacklisted(accounts.bob));
+            assert_eq!(erc20.blacklisted_until(accounts.bob), Some(5));
+
+            // Bob can neither send nor receive within the window
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Blacklisted));
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.charlie, 5),
+                Err(Error::NotOwner)
+            );
+
+            advance_blocks(4);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::Blacklisted)
+            );
+
+            // the sanction lifts at the until block
+            advance_blocks(1);
+            assert!(!erc20.is_blacklisted(accounts.bob));
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+        }
+
+        #[ink::test]
+        fn permanent_blacklist_until_removed() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.bob, BlockNumber::MAX),
+                Ok(())
+            );
+
+            advance_blocks(1_000);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Blacklisted));
+            assert_eq!(erc20.remove_from_blacklist(accounts.bob), Ok(()));
+            assert_eq!(erc20.blacklisted_until(accounts.bob), None);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn observers_registry_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.add_observer(accounts.django), Ok(()));
+            assert_eq!(erc20.add_observer(accounts.frank), Ok(()));
+            assert_eq!(erc20.add_observer(accounts.django), Ok(()));
+            assert_eq!(erc20.observers(), vec![accounts.django, accounts.frank]);
+
+            assert_eq!(erc20.remove_observer(accounts.django), Ok(()));
+            assert_eq!(erc20.observers(), vec![accounts.frank]);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.add_observer(accounts.bob), Err(Error::NotOwner));
+            assert_eq!(erc20.remove_observer(accounts.frank), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn observers_registry_is_capped() {
+            let mut erc20 = Erc20::new(100);
+            for i in 0..MAX_OBSERVERS {
+                assert_eq!(
+                    erc20.add_observer(AccountId::from([0x10 + i as u8; 32])),
+                    Ok(())
+                );
+            }
+            assert_eq!(
+                erc20.add_observer(AccountId::from([0xff; 32])),
+                Err(Error::TooManyObservers)
+            );
+            assert_eq!(erc20.observers().len(), MAX_OBSERVERS);
+        }
+
+        #[ink::test]
+        fn transfer_notifies_observers() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // without observers nothing is called
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+            // the transfer is done and emitted by the time
+            // `notify_transfer()` is called
+            assert_eq!(erc20.add_observer(accounts.django), Ok(()));
+            assert_reaches_contract_call(|| {
+                let _ = erc20.transfer(accounts.bob, 10);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 80);
+            assert_eq!(erc20.balance_of(accounts.bob), 20);
+            assert_eq!(erc20.transfer_count(accounts.alice), 2);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(
+                &emitted_events[2],
+                Some(accounts.alice),
+                Some(accounts.bob),
+                10,
+            );
+        }
+
+        /// Merkle tree of 4 airdrop leaves for Bob, Charlie, Django and Eve,
+        /// return the root and the proof of each leaf
+        fn airdrop_tree(
+            accounts: &ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment>,
+        ) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+            let leaves = [
+                merkle_leaf(accounts.bob, 10),
+                merkle_leaf(accounts.charlie, 20),
+                merkle_leaf(accounts.django, 30),
+                merkle_leaf(accounts.eve, 40),
+            ];
+            let left = hash_pair(&leaves[0], &leaves[1]);
+            let right = hash_pair(&leaves[2], &leaves[3]);
+            let proofs = vec![
+                vec![leaves[1], right],
+                vec![leaves[0], right],
+                vec![leaves[3], left],
+                vec![leaves[2], left],
+            ];
+            (hash_pair(&left, &right), proofs)
+        }
+
+        #[ink::test]
+        fn claim_merkle_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let (root, proofs) = airdrop_tree(&accounts);
+            assert_eq!(erc20.set_merkle_root(root), Ok(()));
+            assert_eq!(erc20.fund_airdrop(100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 900);
+
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.claim_merkle(20, proofs[1].clone()), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert!(erc20.has_claimed_airdrop(accounts.charlie));
+
+            set_caller(accounts.eve);
+            assert_eq!(erc20.claim_merkle(40, proofs[3].clone()), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 40);
+            assert_eq!(erc20.airdrop_pool(), 40);
+            assert_eq!(erc20.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn claim_merkle_rejects_invalid_proofs_and_double_claims() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let (root, proofs) = airdrop_tree(&accounts);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.claim_merkle(10, proofs[0].clone()),
+                Err(Error::NoMerkleRoot)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.set_merkle_root(root), Ok(()));
+            assert_eq!(erc20.fund_airdrop(100), Ok(()));
+
+            set_caller(accounts.bob);
+            // wrong amount, someone else's proof
+            assert_eq!(
+                erc20.claim_merkle(11, proofs[0].clone()),
+                Err(Error::InvalidProof)
+            );
+            assert_eq!(
+                erc20.claim_merkle(10, proofs[1].clone()),
+                Err(Error::InvalidProof)
+            );
+            assert_eq!(erc20.claim_merkle(10, proofs[0].clone()), Ok(()));
+            assert_eq!(
+                erc20.claim_merkle(10, proofs[0].clone()),
+                Err(Error::AlreadyClaimed)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+
+            // Frank isn't part of the tree
+            set_caller(accounts.frank);
+            assert_eq!(
+                erc20.claim_merkle(10, proofs[0].clone()),
+                Err(Error::InvalidProof)
+            );
+            assert_eq!(erc20.airdrop_pool(), 90);
+        }
+
+        #[ink::test]
+        fn claim_merkle_is_bounded_by_pool() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let (root, proofs) = airdrop_tree(&accounts);
+            assert_eq!(erc20.set_merkle_root(root), Ok(()));
+            assert_eq!(erc20.fund_airdrop(30), Ok(()));
+
+            set_caller(accounts.eve);
+            assert_eq!(
+                erc20.claim_merkle(40, proofs[3].clone()),
+                Err(Error::InsufficientAirdropPool)
+            );
+            assert!(!erc20.has_claimed_airdrop(accounts.eve));
+        }
+
+        #[ink::test]
+        fn split_transfer_even_split_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.split_transfer(
+                    100,
+                    vec![
+                        (accounts.bob, 3_334),
+                        (accounts.charlie, 3_333),
+                        (accounts.django, 3_333),
+                    ]
+                ),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 33);
+            assert_eq!(erc20.balance_of(accounts.charlie), 33);
+            // the last recipient gets the rounding leftover
+            assert_eq!(erc20.balance_of(accounts.django), 34);
+            assert_eq!(erc20.balance_of(accounts.alice), 900);
+        }
+
+        #[ink::test]
+        fn split_transfer_uneven_split_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.split_transfer(
+                    999,
+                    vec![
+                        (accounts.bob, 5_000),
+                        (accounts.charlie, 3_000),
+                        (accounts.django, 2_000),
+                    ]
+                ),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 499);
+            assert_eq!(erc20.balance_of(accounts.charlie), 299);
+            assert_eq!(erc20.balance_of(accounts.django), 201);
+            assert_eq!(erc20.balance_of(accounts.alice), 1);
+        }
+
+        #[ink::test]
+        fn split_transfer_fails_without_changes() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.split_transfer(100, vec![(accounts.bob, 5_000), (accounts.charlie, 4_999)]),
+                Err(Error::InvalidSplit)
+            );
+            assert_eq!(erc20.split_transfer(100, vec![]), Err(Error::InvalidSplit));
+            assert_eq!(
+                erc20.split_transfer(1_001, vec![(accounts.bob, 10_000)]),
+                Err(Error::InsufficientBalance)
+            );
+
+            // one blacklisted recipient fails the whole split
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.charlie, BlockNumber::MAX),
+                Ok(())
+            );
+            assert_eq!(
+                erc20.split_transfer(100, vec![(accounts.bob, 5_000), (accounts.charlie, 5_000)]),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn split_transfer_counts_as_one_transfer_for_cooldown() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            set_parameter_now(&mut erc20, ParamKey::CooldownBlocks, 5);
+
+            assert_eq!(
+                erc20.split_transfer(100, vec![(accounts.bob, 5_000), (accounts.charlie, 5_000)]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::CooldownActive));
+        }
+
+        #[ink::test]
+        fn pause_blocks_transfers() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.pause(), Err(Error::NotOwner));
+            ink_env::test::pop_execution_context();
+
+            assert_eq!(erc20.pause(), Ok(()));
+            assert!(erc20.paused());
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::Paused)
+            );
+            ink_env::test::pop_execution_context();
+
+            assert_eq!(erc20.unpause(), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn admin_ops_during_pause_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.set_admin_ops_during_pause(true), Ok(()));
+            assert_eq!(erc20.pause(), Ok(()));
+
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(erc20.mint(accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.burn(100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+            assert_eq!(erc20.total_supply(), 950);
+
+            // regular holders still cannot burn
+            set_caller(accounts.bob);
+            assert_eq!(erc20.burn(10), Err(Error::Paused));
+        }
+
+        #[ink::test]
+        fn admin_ops_blocked_during_pause_by_default() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert!(!erc20.admin_ops_during_pause());
+            assert_eq!(erc20.pause(), Ok(()));
+
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(erc20.mint(accounts.charlie, 50), Err(Error::Paused));
+            assert_eq!(erc20.burn(100), Err(Error::Paused));
+            assert_eq!(erc20.total_supply(), 1_000);
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000);
+        }
+
+        #[ink::test]
+        fn clear_allowances_on_empty_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_clear_allowances_on_empty(true), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.approve(accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, Balance::MAX), Ok(()));
+            assert_eq!(erc20.transfer(accounts.eve, 60), Ok(()));
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 50);
+
+            assert_eq!(erc20.transfer(accounts.eve, 40), Ok(()));
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 0);
+            assert_eq!(erc20.allowance(accounts.bob, accounts.django), 0);
+            ink_env::test::pop_execution_context();
+
+            // a refund doesn't bring the stale approvals back
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                erc20.transfer_from(accounts.bob, accounts.charlie, 10),
+                Err(Error::InsufficientApproval)
+            );
+        }
+
+        #[ink::test]
+        fn cleared_regenerating_allowances_dont_grow_back() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_clear_allowances_on_empty(true), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.approve_regenerating(accounts.charlie, 50, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.eve, 100), Ok(()));
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 0);
+            assert_eq!(
+                erc20.allowance_regeneration(accounts.bob, accounts.charlie),
+                None
+            );
+            ink_env::test::pop_execution_context();
+
+            advance_blocks(5);
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn clear_allowances_on_empty_by_transfer_from() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_clear_allowances_on_empty(true), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.approve(accounts.charlie, 500), Ok(()));
+            ink_env::test::pop_execution_context();
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                erc20.transfer_from_returning(accounts.bob, accounts.charlie, 100),
+                Ok(0)
+            );
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn allowances_kept_on_empty_by_default() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert!(!erc20.clear_allowances_on_empty());
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.approve(accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.transfer(accounts.eve, 100), Ok(()));
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 50);
+        }
+
+        #[ink::test]
+        fn transfer_min_received_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 500);
+            set_parameter_now(&mut erc20, ParamKey::BurnBps, 500);
+
+            // 5% fee and 5% burn leave 90 of 100
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_min_received(accounts.charlie, 100, 90),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.charlie), 90);
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn transfer_min_received_fails_on_slippage() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 500);
+            set_parameter_now(&mut erc20, ParamKey::BurnBps, 500);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_min_received(accounts.charlie, 100, 91),
+                Err(Error::SlippageExceeded)
+            );
+            assert_eq!(erc20.balance_of(accounts.charlie), 0);
+            assert_eq!(erc20.balance_of(accounts.bob), 200);
+            assert_eq!(erc20.balance_of(accounts.alice), 800);
+            assert_eq!(erc20.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn migrate_treasury_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.migrate_treasury(accounts.charlie), Ok(()));
+            assert_eq!(erc20.fee_recipient(), accounts.charlie);
+            assert_eq!(erc20.balance_of(accounts.charlie), 1_000);
+
+            // accumulate some fees on Charlie as the first treasury
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.transfer(accounts.bob, 500), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.eve, 100), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.charlie), 510);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.migrate_treasury(accounts.django),
+                Err(Error::NotOwner)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.migrate_treasury(accounts.django), Ok(()));
+            assert_eq!(erc20.fee_recipient(), accounts.django);
+            assert_eq!(erc20.balance_of(accounts.charlie), 0);
+            assert_eq!(erc20.balance_of(accounts.django), 510);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(
+                &mut &emitted_events[emitted_events.len() - 2].data[..],
+            )
+            .expect("error decoding event data");
+            if let Event::FeeRecipientChanged(FeeRecipientChanged { old, new }) = decoded_event {
+                assert_eq!(old, accounts.charlie);
+                assert_eq!(new, accounts.django);
+            } else {
+                panic!("invalid event type: expected a FeeRecipientChanged event")
+            }
+            assert_transfer_event(
+                &emitted_events[emitted_events.len() - 1],
+                Some(accounts.charlie),
+                Some(accounts.django),
+                510,
+            );
+
+            // future fees land at the new treasury
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.eve, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.django), 520);
+            assert_eq!(erc20.balance_of(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn migrate_treasury_rejects_blacklisted_treasury() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.alice, BlockNumber::MAX),
+                Ok(())
+            );
+            assert_eq!(
+                erc20.migrate_treasury(accounts.bob),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(erc20.remove_from_blacklist(accounts.alice), Ok(()));
+
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.bob, BlockNumber::MAX),
+                Ok(())
+            );
+            assert_eq!(
+                erc20.migrate_treasury(accounts.bob),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(erc20.fee_recipient(), accounts.alice);
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000);
+        }
+
+        #[ink::test]
+        fn mint_batch_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(
+                erc20.mint_batch(vec![
+                    (accounts.bob, 100),
+                    (accounts.charlie, 50),
+                    (accounts.bob, 5)
+                ]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 105);
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+            assert_eq!(erc20.total_supply(), 1_155);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.mint_batch(vec![(accounts.bob, 1)]),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn mint_batch_fails_without_partial_mints() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(
+                erc20.mint_batch(vec![
+                    (accounts.bob, 100),
+                    (accounts.charlie, Balance::MAX - 1_050)
+                ]),
+                Err(Error::Overflow)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.charlie), 0);
+            assert_eq!(erc20.total_supply(), 1_000);
+
+            let mut erc20 = Erc20::new_with_cap(1_000, 1_200, 0, 0);
+            assert_eq!(
+                erc20.mint_batch(vec![(accounts.bob, 100), (accounts.charlie, 101)]),
+                Err(Error::CapExceeded)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn burn_from_treasury_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.grant_role(BURNER_ROLE, accounts.bob), Ok(()));
+            assert!(erc20.has_role(BURNER_ROLE, accounts.bob));
+            assert_eq!(erc20.set_burn_quota(accounts.bob, 300), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.burn_from_treasury(200), Ok(()));
+            assert_eq!(erc20.burn_quota(accounts.bob), 100);
+            assert_eq!(erc20.balance_of(accounts.alice), 800);
+            assert_eq!(erc20.total_supply(), 800);
+
+            assert_eq!(
+                erc20.burn_from_treasury(101),
+                Err(Error::BurnerQuotaExceeded)
+            );
+            assert_eq!(erc20.burn_from_treasury(100), Ok(()));
+            assert_eq!(erc20.burn_quota(accounts.bob), 0);
+            assert_eq!(erc20.burn_from_treasury(1), Err(Error::BurnerQuotaExceeded));
+            assert_eq!(erc20.total_supply(), 700);
+        }
+
+        #[ink::test]
+        fn burn_from_treasury_requires_burner_role() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.set_burn_quota(accounts.bob, 300), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.burn_from_treasury(100), Err(Error::MissingRole));
+            assert_eq!(
+                erc20.grant_role(BURNER_ROLE, accounts.bob),
+                Err(Error::NotOwner)
+            );
+            ink_env::test::pop_execution_context();
+
+            assert_eq!(erc20.grant_role(BURNER_ROLE, accounts.bob), Ok(()));
+            assert_eq!(erc20.revoke_role(BURNER_ROLE, accounts.bob), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.burn_from_treasury(100), Err(Error::MissingRole));
+            assert_eq!(erc20.total_supply(), 1_000);
+        }
+
+        #[ink::test]
+        fn approve_call_before_calls_spender() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // past the deadline the spender isn't called
+            advance_blocks(11);
+            assert_eq!(
+                erc20.approve_call_before(accounts.django, 50, vec![1, 2, 3], 10),
+                Err(Error::DeadlinePassed)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.django), 0);
+
+            // the allowance is approved and emitted by the time
+            // `receive_approval()` is called, which may spend it
+            assert_reaches_contract_call(|| {
+                let _ = erc20.approve_call_before(accounts.django, 50, vec![1, 2, 3], 11);
+            });
+            assert_eq!(erc20.allowance(accounts.alice, accounts.django), 50);
+            assert_eq!(*erc20.reentrant_callee, Some(accounts.django));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("error decoding event data");
+            if let Event::Approval(Approval {
+                owner,
+                spender,
+                value,
+            }) = decoded_event
+            {
+                assert_eq!(owner, accounts.alice);
+                assert_eq!(spender, accounts.django);
+                assert_eq!(value, 50);
+            } else {
+                panic!("invalid event type: expected an Approval event")
+            }
+        }
+
+        #[ink::test]
+        fn approve_call_before_keeps_what_the_spender_spent() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            // steps of approve_call_before() with the spender call skipped,
+            // the spender pulling its whole allowance as `receive_approval()` would
+            let snapshot = erc20.snapshot_allowance(accounts.alice, accounts.bob);
+            erc20.write_approval(accounts.alice, accounts.bob, 50);
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 50),
+                Ok(())
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(
+                erc20.settle_approve_call(accounts.alice, accounts.bob, snapshot, true),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+
+            assert_eq!(erc20.approve(accounts.charlie, 5), Ok(()));
+            let snapshot = erc20.snapshot_allowance(accounts.alice, accounts.charlie);
+            erc20.write_approval(accounts.alice, accounts.charlie, 30);
+            assert_eq!(
+                erc20.settle_approve_call(accounts.alice, accounts.charlie, snapshot, false),
+                Err(Error::CallFailed)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 5);
+        }
+
+        #[ink::test]
+        fn approve_call_before_restores_limited_allowances() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve_with_limit(accounts.bob, 50, 10, 5), Ok(()));
+            assert_eq!(erc20.approve_decaying(accounts.charlie, 40, 10), Ok(()));
+            assert_eq!(erc20.approve_regenerating(accounts.django, 30, 1), Ok(()));
+            let limit = erc20.spending_limit(accounts.alice, accounts.bob);
+            let regeneration = erc20
+                .regenerations
+                .get(&(accounts.alice, accounts.django))
+                .copied();
+            let decay = erc20.allowance_decay(accounts.alice, accounts.charlie);
+            let stats = erc20.storage_stats();
+
+            // steps of approve_call_before() with a failed spender call
+            for spender in [accounts.bob, accounts.charlie, accounts.django] {
+                let snapshot = erc20.snapshot_allowance(accounts.alice, spender);
+                erc20.write_approval(accounts.alice, spender, 0);
+                assert_eq!(
+                    erc20.settle_approve_call(accounts.alice, spender, snapshot, false),
+                    Err(Error::CallFailed)
+                );
+            }
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+            assert_eq!(erc20.spending_limit(accounts.alice, accounts.bob), limit);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 40);
+            assert_eq!(
+                erc20.allowance_decay(accounts.alice, accounts.charlie),
+                decay
+            );
+            assert_eq!(
+                erc20
+                    .regenerations
+                    .get(&(accounts.alice, accounts.django))
+                    .copied(),
+                regeneration
+            );
+            assert_eq!(erc20.storage_stats(), stats);
+            assert_eq!(
+                erc20.owner_spenders.get(&accounts.alice),
+                Some(&vec![accounts.bob, accounts.charlie, accounts.django])
+            );
+        }
+
+        #[ink::test]
+        fn approve_call_before_fails_after_deadline() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            advance_blocks(3);
+
+            assert_eq!(
+                erc20.approve_call_before(accounts.django, 50, vec![1, 2, 3], 2),
+                Err(Error::DeadlinePassed)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.django), 0);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn supply_history_records_mints_and_burns() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.supply_history_page(0, 10), vec![]);
+
+            assert_eq!(erc20.mint(accounts.bob, 100), Ok(()));
+            advance_blocks(2);
+            assert_eq!(erc20.burn(50), Ok(()));
+            advance_blocks(1);
+            set_parameter_now(&mut erc20, ParamKey::BurnBps, 1_000);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+
+            assert_eq!(
+                erc20.supply_history_page(0, 10),
+                vec![
+                    (0, 1_100),
+                    (2, 1_050),
+                    (3 + MIN_PARAMETER_DELAY_BLOCKS, 1_040)
+                ]
+            );
+            assert_eq!(erc20.supply_history_page(1, 1), vec![(2, 1_050)]);
+            assert_eq!(erc20.supply_history_page(3, 10), vec![]);
+        }
+
+        #[ink::test]
+        fn supply_history_drops_oldest_entries() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(0);
+            for _ in 0..MAX_SUPPLY_HISTORY + 2 {
+                assert_eq!(erc20.mint(accounts.bob, 1), Ok(()));
+            }
+
+            let history = erc20.supply_history_page(0, u32::MAX);
+            assert_eq!(history.len(), MAX_SUPPLY_HISTORY);
+            assert_eq!(history[0], (0, 3));
+            assert_eq!(
+                history[MAX_SUPPLY_HISTORY - 1],
+                (0, MAX_SUPPLY_HISTORY as Balance + 2)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_idempotent_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.transfer_idempotent(accounts.bob, 100, [1; 32]),
+                Ok(())
+            );
+            assert_eq!(
+                erc20.transfer_idempotent(accounts.bob, 100, [1; 32]),
+                Err(Error::DuplicateRequest)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+
+            assert_eq!(
+                erc20.transfer_idempotent(accounts.bob, 100, [2; 32]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 200);
+
+            // keys are per sender
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_idempotent(accounts.charlie, 10, [1; 32]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+        }
+
+        #[ink::test]
+        fn transfer_idempotent_failure_does_not_use_key() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.transfer_idempotent(accounts.bob, 1_001, [1; 32]),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(
+                erc20.transfer_idempotent(accounts.bob, 1_000, [1; 32]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 1_000);
+        }
+
+        #[ink::test]
+        fn fee_rounding_toward_treasury_rounds_up() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new_with_fee_rounding(1_000, RoundingMode::TowardTreasury);
+            assert_eq!(erc20.fee_rounding(), RoundingMode::TowardTreasury);
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+
+            // a 1.5 tokens fee rounds up to 2
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 15), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 13);
+            assert_eq!(erc20.balance_of(accounts.alice), 902);
+
+            // whole fees are not rounded
+            assert_eq!(erc20.transfer(accounts.charlie, 20), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 31);
+            assert_eq!(erc20.balance_of(accounts.alice), 904);
+        }
+
+        #[ink::test]
+        fn fee_rounding_toward_sender_rounds_down() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new_with_fee_rounding(1_000, RoundingMode::TowardSender);
+            assert_eq!(erc20.fee_rounding(), RoundingMode::TowardSender);
+            assert_eq!(Erc20::new(1_000).fee_rounding(), RoundingMode::TowardSender);
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+
+            // a 1.5 tokens fee rounds down to 1
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 15), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 14);
+            assert_eq!(erc20.balance_of(accounts.alice), 901);
+        }
+
+        #[ink::test]
+        fn claim_merkle_rejected_after_deadline() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let (root, proofs) = airdrop_tree(&accounts);
+            assert_eq!(erc20.set_merkle_root(root), Ok(()));
+            assert_eq!(erc20.fund_airdrop(100), Ok(()));
+            assert_eq!(erc20.set_claim_deadline(5), Ok(()));
+            assert_eq!(erc20.reclaim_airdrop(), Err(Error::ClaimPeriodActive));
+
+            advance_blocks(5);
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.claim_merkle(20, proofs[1].clone()), Ok(()));
+            ink_env::test::pop_execution_context();
+
+            advance_blocks(1);
+            set_caller(accounts.eve);
+            assert_eq!(
+                erc20.claim_merkle(40, proofs[3].clone()),
+                Err(Error::ClaimPeriodEnded)
+            );
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+            assert_eq!(erc20.airdrop_pool(), 80);
+        }
+
+        #[ink::test]
+        fn reclaim_airdrop_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let (root, proofs) = airdrop_tree(&accounts);
+            assert_eq!(erc20.set_merkle_root(root), Ok(()));
+            assert_eq!(erc20.fund_airdrop(100), Ok(()));
+            assert_eq!(erc20.set_claim_deadline(5), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.claim_merkle(20, proofs[1].clone()), Ok(()));
+            ink_env::test::pop_execution_context();
+
+            advance_blocks(6);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.reclaim_airdrop(), Err(Error::NotOwner));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.reclaim_airdrop(), Ok(()));
+            assert_eq!(erc20.airdrop_pool(), 0);
+            assert_eq!(erc20.merkle_root(), None);
+            assert_eq!(erc20.balance_of(accounts.alice), 980);
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            let contract = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get contract account");
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(
+                emitted_events.last().unwrap(),
+                Some(contract),
+                Some(accounts.alice),
+                80,
+            );
+
+            // claims stay disabled even without the deadline
+            assert_eq!(erc20.set_claim_deadline(0), Ok(()));
+            set_caller(accounts.eve);
+            assert_eq!(
+                erc20.claim_merkle(40, proofs[3].clone()),
+                Err(Error::NoMerkleRoot)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_with_vault_skips_vault_when_not_short() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_vault(Some(accounts.django)), Ok(()));
+            assert_eq!(erc20.vault(), Some(accounts.django));
+
+            assert_eq!(erc20.transfer_with_vault(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn transfer_with_vault_redeems_shortfall() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_vault(Some(accounts.django)), Ok(()));
+
+            // without a shortfall the vault isn't called
+            assert_eq!(erc20.transfer_with_vault(accounts.bob, 60), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 60);
+
+            // `redeem()` is called before anything moves
+            assert_reaches_contract_call(|| {
+                let _ = erc20.transfer_with_vault(accounts.bob, 150);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 40);
+            assert_eq!(erc20.balance_of(accounts.bob), 60);
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        fn transfer_with_vault_fails_without_vault() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.transfer_with_vault(accounts.bob, 150),
+                Err(Error::VaultRedeemFailed)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn allow_burn_during_pause_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert!(!erc20.allow_burn_during_pause());
+            assert_eq!(erc20.set_allow_burn_during_pause(true), Ok(()));
+            assert_eq!(erc20.pause(), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Err(Error::Paused));
+            assert_eq!(erc20.burn(40), Ok(()));
+            assert_eq!(erc20.burn_and_lower_cap(10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+            assert_eq!(erc20.total_supply(), 950);
+            ink_env::test::pop_execution_context();
+
+            // minting stays blocked
+            assert_eq!(erc20.mint(accounts.bob, 10), Err(Error::Paused));
+
+            assert_eq!(erc20.set_allow_burn_during_pause(false), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.burn(10), Err(Error::Paused));
+        }
+
+        #[ink::test]
+        fn fee_swap_waits_for_threshold() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.migrate_treasury(accounts.eve), Ok(()));
+            set_caller(accounts.eve);
+            assert_eq!(erc20.transfer(accounts.bob, 1_000), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            assert_eq!(erc20.set_fee_swap(Some(accounts.django), 20), Ok(()));
+            assert_eq!(erc20.fee_swap(), (Some(accounts.django), 20));
+
+            // the treasury holds 10 of the 20 tokens needed, no swap is called
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 10);
+        }
+
+        #[ink::test]
+        fn fee_swap_triggers_at_threshold() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.migrate_treasury(accounts.eve), Ok(()));
+            set_caller(accounts.eve);
+            assert_eq!(erc20.transfer(accounts.bob, 1_000), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            assert_eq!(erc20.set_fee_swap(Some(accounts.django), 20), Ok(()));
+
+            // below the threshold the router isn't called
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 10);
+
+            // the fee is collected and the swap guard held by the time
+            // `swap_tokens_for_native()` is called
+            assert_reaches_contract_call(|| {
+                let _ = erc20.transfer(accounts.charlie, 100);
+            });
+            assert_eq!(erc20.balance_of(accounts.bob), 800);
+            assert_eq!(erc20.balance_of(accounts.charlie), 180);
+            assert_eq!(erc20.balance_of(accounts.eve), 20);
+            assert!(*erc20.in_swap);
+            // only the router may enter again
+            assert_eq!(*erc20.reentrant_callee, Some(accounts.django));
+        }
+
+        #[ink::test]
+        fn fee_swap_skipped_during_swap_and_for_amm_pairs() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.migrate_treasury(accounts.eve), Ok(()));
+            set_caller(accounts.eve);
+            assert_eq!(erc20.transfer(accounts.bob, 1_000), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            assert_eq!(erc20.set_fee_swap(Some(accounts.django), 10), Ok(()));
+            assert_eq!(erc20.set_amm_pair(accounts.bob, true), Ok(()));
+            assert!(erc20.is_amm_pair(accounts.bob));
+
+            // transfers from the pair never swap
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 20);
+            ink_env::test::pop_execution_context();
+
+            // neither do the transfers made by a running swap
+            Lazy::set(&mut erc20.in_swap, true);
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.transfer(accounts.frank, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 30);
+        }
+
+        #[ink::test]
+        fn split_transfer_ten_ways_works() {
+            let mut erc20 = Erc20::new(10_000);
+            let recipients: Vec<(AccountId, u16)> = (1..=10u8)
+                .map(|i| (AccountId::from([i + 0x10; 32]), 1_000))
+                .collect();
+
+            assert_eq!(erc20.split_transfer(1_009, recipients.clone()), Ok(()));
+            for (to, _) in recipients[..9].iter() {
+                assert_eq!(erc20.balance_of(*to), 100);
+            }
+            assert_eq!(erc20.balance_of(recipients[9].0), 109);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.balance_of(accounts.alice), 8_991);
+            assert_eq!(erc20.total_supply(), 10_000);
+            // one Transfer event per share after the initial mint
+            assert_eq!(ink_env::test::recorded_events().count(), 11);
+        }
+
+        #[ink::test]
+        fn split_transfer_charges_the_fee_once() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            set_parameter_now(&mut erc20, ParamKey::MinFee, 5);
+
+            // 10% of the 100 tokens rather than the min fee of every share
+            set_caller(accounts.bob);
+            let recipients = vec![
+                (accounts.charlie, 3_000),
+                (accounts.django, 3_000),
+                (accounts.eve, 4_000),
+            ];
+            assert_eq!(erc20.split_transfer(100, recipients), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 27);
+            assert_eq!(erc20.balance_of(accounts.django), 27);
+            assert_eq!(erc20.balance_of(accounts.eve), 36);
+            assert_eq!(erc20.balance_of(accounts.alice), 810);
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+            assert_eq!(erc20.transfer_count(accounts.bob), 1);
+
+            // a share sent to the fee recipient isn't charged
+            let recipients = vec![(accounts.alice, 5_000), (accounts.charlie, 5_000)];
+            assert_eq!(erc20.split_transfer(100, recipients), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 865);
+            assert_eq!(erc20.balance_of(accounts.charlie), 72);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let len = emitted_events.len();
+            assert_transfer_event(
+                &emitted_events[len - 1],
+                Some(accounts.bob),
+                Some(accounts.alice),
+                5,
+            );
+        }
+
+        #[ink::test]
+        fn balance_share_bps_works() {
+            let mut erc20 = Erc20::new(3_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 1_000), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 1), Ok(()));
+
+            assert_eq!(erc20.balance_share_bps(accounts.alice), 6_663);
+            assert_eq!(erc20.balance_share_bps(accounts.bob), 3_333);
+            assert_eq!(erc20.balance_share_bps(accounts.charlie), 3);
+            assert_eq!(erc20.balance_share_bps(accounts.django), 0);
+
+            assert_eq!(erc20.burn(1_999), Ok(()));
+            assert_eq!(erc20.balance_share_bps(accounts.bob), 9_990);
+        }
+
+        #[ink::test]
+        fn balance_share_bps_handles_edge_supplies() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let erc20 = Erc20::new(0);
+            assert_eq!(erc20.balance_share_bps(accounts.alice), 0);
+
+            // the product would overflow a Balance
+            let mut erc20 = Erc20::new(Balance::MAX);
+            assert_eq!(erc20.balance_share_bps(accounts.alice), 10_000);
+            assert_eq!(erc20.transfer(accounts.bob, Balance::MAX / 4), Ok(()));
+            assert_eq!(erc20.balance_share_bps(accounts.bob), 2_499);
+            assert_eq!(erc20.balance_share_bps(accounts.alice), 7_500);
+        }
+
+        #[test]
+        fn mul_div_works() {
+            assert_eq!(mul_div(7, 3, 2), 10);
+            assert_eq!(mul_div(1, 10_000, 3), 3_333);
+            assert_eq!(mul_div(Balance::MAX, 10_000, Balance::MAX), 10_000);
+            assert_eq!(
+                mul_div(Balance::MAX - 1, Balance::from(u16::MAX), Balance::MAX),
+                65_534
+            );
+            assert_eq!(
+                mul_div(Balance::MAX, Balance::MAX - 1, Balance::MAX),
+                Balance::MAX - 1
+            );
+        }
+
+        #[ink::test]
+        fn burn_from_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.burn_from(accounts.alice, 101),
+                Err(Error::InsufficientApproval)
+            );
+            assert_eq!(erc20.burn_from(accounts.alice, 60), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+            assert_eq!(erc20.balance_of(accounts.alice), 940);
+            assert_eq!(erc20.total_supply(), 940);
+
+            // burning exactly the rest removes the allowance entry
+            assert_eq!(erc20.burn_from(accounts.alice, 40), Ok(()));
+            assert!(!erc20
+                .allowances
+                .contains_key(&(accounts.alice, accounts.bob)));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(erc20.total_supply(), 900);
+        }
+
+        #[ink::test]
+        fn transfer_from_removes_consumed_allowance() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 100),
+                Ok(())
+            );
+            assert!(!erc20
+                .allowances
+                .contains_key(&(accounts.alice, accounts.bob)));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            ink_env::test::pop_execution_context();
+
+            assert_eq!(erc20.approve(accounts.bob, Balance::MAX), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.burn_from(accounts.alice, 100), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn sniper_tax_decays_over_the_window() {
+            let mut erc20 = Erc20::new(10_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 5_000), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 100);
+            assert_eq!(erc20.set_sniper_tax(10, 5_000), Ok(()));
+            assert_eq!(erc20.sniper_tax(), (10, 5_000));
+            assert_eq!(erc20.enable_trading(), Ok(()));
+            assert_eq!(erc20.launch_block(), Some(MIN_PARAMETER_DELAY_BLOCKS));
+            assert_eq!(erc20.enable_trading(), Err(Error::TradingAlreadyEnabled));
+
+            // at launch the 1% fee comes with a 50% tax
+            assert_eq!(
+                erc20.preview_transfer(accounts.bob, accounts.charlie, 1_000),
+                TransferPreview {
+                    net_to_recipient: 490,
+                    fee: 10,
+                    burn: 0,
+                    tax: 500,
+                    reflection: 0,
+                }
+            );
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 1_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 490);
+            assert_eq!(erc20.balance_of(accounts.alice), 5_510);
+            ink_env::test::pop_execution_context();
+
+            // halfway the tax is halved
+            advance_blocks(5);
+            assert_eq!(
+                erc20
+                    .preview_transfer(accounts.bob, accounts.charlie, 1_000)
+                    .tax,
+                250
+            );
+
+            // then only the normal fee is left
+            advance_blocks(5);
+            assert_eq!(
+                erc20.preview_transfer(accounts.bob, accounts.charlie, 1_000),
+                TransferPreview {
+                    net_to_recipient: 990,
+                    fee: 10,
+                    burn: 0,
+                    tax: 0,
+                    reflection: 0,
+                }
+            );
+        }
+
+        #[ink::test]
+        fn sniper_tax_needs_enable_trading() {
+            let mut erc20 = Erc20::new(10_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                erc20.set_sniper_tax(10, 10_001),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.set_sniper_tax(10, 5_000), Ok(()));
+            assert_eq!(erc20.launch_block(), None);
+            assert_eq!(
+                erc20
+                    .preview_transfer(accounts.bob, accounts.charlie, 1_000)
+                    .tax,
+                0
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.enable_trading(), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn batch_transfer_emits_transfer_per_recipient_by_default() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert!(!erc20.batch_events());
+
+            assert_eq!(
+                erc20.batch_transfer(vec![
+                    (accounts.bob, 10),
+                    (accounts.charlie, 20),
+                    (accounts.django, 30),
+                ]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.balance_of(accounts.alice), 940);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+            assert_transfer_event(
+                &emitted_events[3],
+                Some(accounts.alice),
+                Some(accounts.django),
+                30,
+            );
+
+            assert_eq!(
+                erc20.airdrop_equal(vec![accounts.bob, accounts.eve], 5),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 15);
+            assert_eq!(erc20.balance_of(accounts.eve), 5);
+            assert_eq!(ink_env::test::recorded_events().count(), 6);
+        }
+
+        #[ink::test]
+        fn batch_events_aggregate_batch_transfers() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_batch_events(true), Ok(()));
+
+            assert_eq!(
+                erc20.airdrop_equal(vec![accounts.bob, accounts.charlie, accounts.django], 10),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.django), 10);
+            assert_eq!(erc20.balance_of(accounts.alice), 970);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("error decoding event data");
+            if let Event::BatchTransfer(BatchTransfer { from, total, count }) = decoded_event {
+                assert_eq!(from, accounts.alice);
+                assert_eq!(total, 30);
+                assert_eq!(count, 3);
+            } else {
+                panic!("invalid event type: expected a BatchTransfer event")
+            }
+        }
+
+        #[ink::test]
+        fn batch_transfer_fails_without_changes() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(
+                erc20.batch_transfer(vec![(accounts.bob, 600), (accounts.charlie, 401)]),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(
+                erc20.batch_transfer(vec![(accounts.bob, 1), (accounts.charlie, Balance::MAX)]),
+                Err(Error::Overflow)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn owner_recovery_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_recovery_guardian(Some(accounts.bob), 10), Ok(()));
+            assert_eq!(erc20.recovery_guardian(), (Some(accounts.bob), 10));
+            assert_eq!(
+                erc20.propose_owner_recovery(accounts.charlie),
+                Err(Error::NotGuardian)
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.execute_owner_recovery(),
+                Err(Error::NoPendingRecovery)
+            );
+            assert_eq!(erc20.propose_owner_recovery(accounts.charlie), Ok(()));
+            assert_eq!(erc20.pending_recovery(), Some((accounts.charlie, 10)));
+
+            advance_blocks(9);
+            assert_eq!(
+                erc20.execute_owner_recovery(),
+                Err(Error::RecoveryDelayActive)
+            );
+            assert_eq!(erc20.owner(), accounts.alice);
+
+            advance_blocks(1);
+            assert_eq!(erc20.execute_owner_recovery(), Ok(()));
+            assert_eq!(erc20.owner(), accounts.charlie);
+            assert_eq!(erc20.pending_recovery(), None);
+            ink_env::test::pop_execution_context();
+
+            assert_eq!(erc20.pause(), Err(Error::NotOwner));
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.pause(), Ok(()));
+        }
+
+        #[ink::test]
+        fn owner_recovery_cancelled_by_owner() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_recovery_guardian(Some(accounts.bob), 10), Ok(()));
+            assert_eq!(erc20.cancel_owner_recovery(), Err(Error::NoPendingRecovery));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.propose_owner_recovery(accounts.charlie), Ok(()));
+            assert_eq!(erc20.cancel_owner_recovery(), Err(Error::NotOwner));
+            ink_env::test::pop_execution_context();
+
+            advance_blocks(5);
+            assert_eq!(erc20.cancel_owner_recovery(), Ok(()));
+            advance_blocks(5);
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.execute_owner_recovery(),
+                Err(Error::NoPendingRecovery)
+            );
+            assert_eq!(erc20.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn tax_burn_share_splits_the_tax() {
+            let mut erc20 = Erc20::new(10_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 5_000), Ok(()));
+            assert_eq!(
+                erc20.set_parameter(ParamKey::TaxBurnShareBps, 10_001),
+                Err(Error::InvalidParameter)
+            );
+            set_parameter_now(&mut erc20, ParamKey::TaxBurnShareBps, 5_000);
+            assert_eq!(erc20.parameter(ParamKey::TaxBurnShareBps), 5_000);
+            assert_eq!(erc20.set_sniper_tax(10, 5_000), Ok(()));
+            assert_eq!(erc20.enable_trading(), Ok(()));
+
+            // half of the 500 tokens tax is burned, half goes to the treasury
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 1_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 500);
+            assert_eq!(erc20.balance_of(accounts.alice), 5_250);
+            assert_eq!(erc20.total_supply(), 9_750);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let len = emitted_events.len();
+            assert_transfer_event(
+                &emitted_events[len - 2],
+                Some(accounts.bob),
+                Some(accounts.alice),
+                250,
+            );
+            assert_transfer_event(&emitted_events[len - 1], Some(accounts.bob), None, 250);
+        }
+
+        #[ink::test]
+        fn approve_with_limit_caps_spending_per_period() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                erc20.approve_with_limit(accounts.bob, 500, 100, 0),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.approve_with_limit(accounts.bob, 500, 100, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 500);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 60),
+                Ok(())
+            );
+            assert_eq!(erc20.burn_from(accounts.alice, 40), Ok(()));
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 1),
+                Err(Error::PeriodLimitExceeded)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 400);
+
+            advance_blocks(9);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 1),
+                Err(Error::PeriodLimitExceeded)
+            );
+
+            // a new period starts
+            advance_blocks(1);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 100),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 160);
+            assert_eq!(
+                erc20.spending_limit(accounts.alice, accounts.bob),
+                Some(SpendingLimit {
+                    per_period_limit: 100,
+                    period_blocks: 10,
+                    spent_this_period: 100,
+                    period_start: 10,
+                })
+            );
+        }
+
+        #[ink::test]
+        fn approve_removes_spending_limit() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve_with_limit(accounts.bob, 500, 100, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 500), Ok(()));
+            assert_eq!(erc20.spending_limit(accounts.alice, accounts.bob), None);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 500),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn storage_stats_track_map_entries() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.storage_stats().holder_count, 1);
+
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.storage_stats().holder_count, 3);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.storage_stats().holder_count, 2);
+
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.storage_stats().allowance_count, 2);
+            assert_eq!(erc20.approve(accounts.charlie, 0), Ok(()));
+            assert_eq!(erc20.storage_stats().allowance_count, 1);
+
+            assert_eq!(erc20.add_to_blacklist(accounts.eve, 5), Ok(()));
+            assert_eq!(erc20.add_to_blacklist(accounts.eve, 10), Ok(()));
+            assert_eq!(erc20.add_to_blacklist(accounts.frank, 10), Ok(()));
+            assert_eq!(erc20.remove_from_blacklist(accounts.frank), Ok(()));
+            assert_eq!(erc20.remove_from_blacklist(accounts.frank), Ok(()));
+            assert_eq!(erc20.storage_stats().blacklist_count, 1);
+
+            assert_eq!(erc20.create_vesting(accounts.django, 100, 0, 10), Ok(()));
+            assert_eq!(
+                erc20.storage_stats(),
+                StorageStats {
+                    holder_count: 2,
+                    allowance_count: 1,
+                    blacklist_count: 1,
+                    vesting_count: 1,
+                }
+            );
+        }
+
+        #[ink::test]
+        fn approve_regenerating_refills_allowance() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve_regenerating(accounts.bob, 100, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 100);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 100),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 1),
+                Err(Error::InsufficientApproval)
+            );
+
+            advance_blocks(3);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 30);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 25),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 5);
+
+            // the refill stops at the max
+            advance_blocks(20);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 100);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 100),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 225);
+        }
+
+        #[ink::test]
+        fn approve_stops_regeneration() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve_regenerating(accounts.bob, 100, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 20), Ok(()));
+            assert_eq!(
+                erc20.allowance_regeneration(accounts.alice, accounts.bob),
+                None
+            );
+
+            advance_blocks(5);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+        }
+
+        #[test]
+        fn error_codes_and_messages_are_stable() {
+            let errors = [
+                Error::InsufficientBalance,
+                Error::InsufficientApproval,
+                Error::NotGovernor,
+                Error::InvalidParameter,
+                Error::MaxTxExceeded,
+                Error::CooldownActive,
+                Error::ClaimNotFound,
+                Error::NotClaimRecipient,
+                Error::NotClaimSender,
+                Error::NotOwner,
+                Error::CapExceeded,
+                Error::InsufficientStake,
+                Error::VestingExists,
+                Error::NoVesting,
+                Error::NoSubscription,
+                Error::IntervalNotElapsed,
+                Error::DuplicateRecipient,
+                Error::Overflow,
+                Error::VolumeCapExceeded,
+                Error::NotWhitelisted,
+                Error::Blacklisted,
+                Error::TooManyObservers,
+                Error::NoMerkleRoot,
+                Error::InvalidProof,
+                Error::AlreadyClaimed,
+                Error::InsufficientAirdropPool,
+                Error::InvalidSplit,
+                Error::Paused,
+                Error::SlippageExceeded,
+                Error::MissingRole,
+                Error::BurnerQuotaExceeded,
+                Error::DeadlinePassed,
+                Error::CallFailed,
+                Error::DuplicateRequest,
+                Error::ClaimPeriodEnded,
+                Error::ClaimPeriodActive,
+                Error::VaultRedeemFailed,
+                Error::TradingAlreadyEnabled,
+                Error::NotGuardian,
+                Error::NoPendingRecovery,
+                Error::RecoveryDelayActive,
+                Error::PeriodLimitExceeded,
+                Error::BatchTooLarge,
+                Error::TokensLocked,
+                Error::RedeemFailed,
+                Error::ApprovalTooLarge,
+                Error::RecipientNotOptedIn,
+                Error::NotOracle,
+                Error::DailyLimitExceeded,
+                Error::SpenderNotAccepted,
+                Error::TooManySpenders,
+                Error::VestingNotRevocable,
+                Error::MintProposalNotFound,
+                Error::AlreadyApproved,
+                Error::WrapFailed,
+                Error::StakingPaused,
+                Error::ConversionDisabled,
+                Error::QueuedTransferNotFound,
+                Error::TimelockActive,
+                Error::TransferExpired,
+                Error::TransferNotExpired,
+                Error::NoPendingParameter,
+                Error::Reentrancy,
+                Error::NotTrusted,
+            ];
+            for (i, error) in errors.iter().enumerate() {
+                assert_eq!(error.code() as usize, i + 1);
+                assert!(!error.as_str().is_empty());
+                assert!(errors[..i]
+                    .iter()
+                    .all(|other| other.as_str() != error.as_str()));
+            }
+            assert_eq!(Error::InsufficientBalance.code(), 1);
+            assert_eq!(Error::NotOwner.code(), 10);
+            assert_eq!(Error::NotOwner.as_str(), "caller is not the owner");
+            assert_eq!(Error::Paused.code(), 28);
+            assert_eq!(Error::Paused.as_str(), "contract paused");
+        }
+
+        #[ink::test]
+        fn export_and_import_allowances_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 20), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.approve(accounts.django, 30), Ok(()));
+            assert_eq!(erc20.export_allowances(0, 10), Err(Error::NotOwner));
+            ink_env::test::pop_execution_context();
+
+            let mut entries = erc20.export_allowances(0, 2).expect("owner can export");
+            assert_eq!(entries.len(), 2);
+            entries.extend(erc20.export_allowances(2, 2).expect("owner can export"));
+            assert_eq!(entries.len(), 3);
+
+            let mut migrated = Erc20::new(1_000);
+            assert_eq!(migrated.import_allowances(entries), Ok(()));
+            for (owner, spender) in [
+                (accounts.alice, accounts.bob),
+                (accounts.alice, accounts.charlie),
+                (accounts.bob, accounts.django),
+            ] {
+                assert_eq!(
+                    migrated.allowance(owner, spender),
+                    erc20.allowance(owner, spender)
+                );
+            }
+            assert_eq!(migrated.allowance(accounts.bob, accounts.django), 30);
+        }
+
+        #[ink::test]
+        fn import_allowances_only_before_trading() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.enable_trading(), Ok(()));
+
+            assert_eq!(
+                erc20.import_allowances(vec![((accounts.bob, accounts.charlie), 10)]),
+                Err(Error::TradingAlreadyEnabled)
+            );
+            assert_eq!(erc20.allowance(accounts.bob, accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn cooldown_exempts_sells_to_amm_pairs() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::CooldownBlocks, 5);
+            assert_eq!(erc20.set_amm_pair(accounts.django, true), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.eve, 10), Err(Error::CooldownActive));
+            // selling to the pair isn't throttled
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.django), 20);
+            assert_eq!(erc20.transfer(accounts.eve, 10), Err(Error::CooldownActive));
+            assert_eq!(
+                erc20.split_transfer(10, vec![(accounts.django, 5_000), (accounts.eve, 5_000)]),
+                Err(Error::CooldownActive)
+            );
+        }
+
+        #[ink::test]
+        fn migrate_to_sends_balances_to_new_contract() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+            // `receive_migration()` is called before any balance is zeroed,
+            // so a failing receiver leaves them all in place
+            assert_reaches_contract_call(|| {
+                let _ = erc20.migrate_to(accounts.django, 10, true);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 900);
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+            assert_eq!(erc20.total_supply(), 1_000);
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        fn migrate_to_only_by_owner() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.migrate_to(accounts.django, 10, true),
+                Err(Error::NotOwner)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000);
+
+            // an empty batch is a no-op without `terminate`
+            let mut erc20 = Erc20::new(0);
+            assert_eq!(erc20.migrate_to(accounts.django, 10, false), Ok(()));
+        }
+
+        #[ink::test]
+        fn migrate_to_keeps_the_contract_while_positions_remain() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.stake(60), Ok(()));
+            assert_eq!(erc20.create_claim(accounts.bob, 40), Ok(0));
+            assert_eq!(erc20.balance_of(accounts.alice), 0);
+
+            // no liquid holder is left, but the stake and the escrow would be lost
+            assert_eq!(erc20.migrate_to(accounts.django, 10, true), Ok(()));
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.staked_balance_of(accounts.alice), 60);
+            assert_eq!(erc20.get_claim(0), Some((accounts.alice, accounts.bob, 40)));
+        }
+
+        #[ink::test]
+        fn migrate_to_terminates_once_drained() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let erc20 = Erc20::new(0);
+            let contract_balance = ink_env::balance::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get contract balance");
+
+            let mut erc20 = std::panic::AssertUnwindSafe(erc20);
+            let new_contract = accounts.django;
+            let should_terminate = move || {
+                let _ = erc20.migrate_to(new_contract, 10, true);
+            };
+            ink_env::test::assert_contract_termination::<ink_env::DefaultEnvironment, _>(
+                should_terminate,
+                accounts.alice,
+                contract_balance,
+            );
+        }
+
+        #[ink::test]
+        fn max_batch_size_bounds_batch_transfers() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.max_batch_size(), DEFAULT_MAX_BATCH_SIZE);
+            assert_eq!(erc20.set_max_batch_size(0), Err(Error::InvalidParameter));
+            assert_eq!(erc20.set_max_batch_size(2), Ok(()));
+
+            assert_eq!(
+                erc20.batch_transfer(vec![(accounts.bob, 10), (accounts.charlie, 20)]),
+                Ok(())
+            );
+            assert_eq!(
+                erc20.airdrop_equal(vec![accounts.bob, accounts.charlie, accounts.django], 10),
+                Err(Error::BatchTooLarge)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 970);
+            assert_eq!(erc20.balance_of(accounts.django), 0);
+            assert_eq!(ink_env::test::recorded_events().count(), 3);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_max_batch_size(10), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn total_balance_of_includes_releasable_vesting() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 5), Ok(()));
+            assert_eq!(erc20.create_vesting(accounts.bob, 40, 0, 20), Ok(()));
+            assert_eq!(erc20.total_balance_of(accounts.bob), 5);
+
+            advance_blocks(5);
+            assert_eq!(erc20.balance_of(accounts.bob), 5);
+            assert_eq!(erc20.total_balance_of(accounts.bob), 15);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 15);
+            assert_eq!(erc20.total_balance_of(accounts.bob), 15);
+        }
+
+        #[ink::test]
+        fn zero_approval_emits_approval_revoked() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+
+            assert_eq!(erc20.approve(accounts.bob, 0), Ok(()));
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[3].data[..])
+                .expect("error decoding event data");
+            if let Event::ApprovalRevoked(ApprovalRevoked { owner, spender }) = decoded_event {
+                assert_eq!(owner, accounts.alice);
+                assert_eq!(spender, accounts.bob);
+            } else {
+                panic!("invalid event type: expected an ApprovalRevoked event")
+            }
+        }
+
+        #[ink::test]
+        fn new_with_lock_locks_the_deployer() {
+            let mut erc20 = Erc20::new_with_lock(100, 10);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.deployer_unlock_block(), 10);
+            assert_eq!(erc20.lock_until(5), Err(Error::InvalidParameter));
+
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::TokensLocked));
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::TokensLocked)
+            );
+            ink_env::test::pop_execution_context();
+
+            advance_blocks(10);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn lock_until_locks_own_tokens() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.deployer_unlock_block(), 0);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.lock_until(3), Ok(()));
+            assert_eq!(erc20.unlock_block_of(accounts.bob), 3);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 1),
+                Err(Error::TokensLocked)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.transfer(accounts.charlie, 1), Ok(()));
+
+            advance_blocks(3);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn balance_of_formatted_truncates_to_display_decimals() {
+            let mut erc20 = Erc20::new_with_decimals(123_456_789, 8);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.decimals(), 8);
+            assert_eq!(erc20.display_decimals(), 8);
+            assert_eq!(erc20.balance_of_formatted(accounts.alice), "1.23456789");
+
+            assert_eq!(
+                erc20.set_display_decimals(Some(9)),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.set_display_decimals(Some(2)), Ok(()));
+            assert_eq!(erc20.balance_of_formatted(accounts.alice), "1.23");
+            assert_eq!(erc20.balance_of_formatted(accounts.bob), "0.00");
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_display_decimals(None), Err(Error::NotOwner));
+        }
+
+        #[test]
+        fn format_amount_works() {
+            assert_eq!(format_amount(1_050, 3, 3), "1.050");
+            assert_eq!(format_amount(1_050, 3, 1), "1.0");
+            assert_eq!(format_amount(1_050, 3, 0), "1");
+            assert_eq!(format_amount(42, 0, 0), "42");
+            assert_eq!(format_amount(7, 4, 4), "0.0007");
+        }
+
+        #[ink::test]
+        fn redeem_queries_the_backing_token() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_backing_token(Some(accounts.django)), Ok(()));
+            assert_eq!(erc20.backing_token(), Some(accounts.django));
+
+            // `balance_of()` prices the redemption before anything is
+            // burned or guarded
+            assert_reaches_contract_call(|| {
+                let _ = erc20.redeem(10);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.total_supply(), 100);
+            assert!(!*erc20.in_redeem);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[test]
+        fn redeemed_share_works() {
+            // 10 of 100 tokens redeem 10% of the holdings, rounding down
+            assert_eq!(redeemed_share(1_000, 10, 100), Some(100));
+            assert_eq!(redeemed_share(999, 10, 100), Some(99));
+            assert_eq!(redeemed_share(1_000, 0, 100), Some(0));
+            assert_eq!(redeemed_share(1_000, 10, 0), Some(0));
+            assert_eq!(redeemed_share(Balance::MAX, 2, 4), None);
+        }
+
+        #[ink::test]
+        fn redeem_fails_without_changes() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.redeem(10), Err(Error::RedeemFailed));
+
+            assert_eq!(erc20.set_backing_token(Some(accounts.django)), Ok(()));
+            assert_eq!(erc20.redeem(101), Err(Error::InsufficientBalance));
+            Lazy::set(&mut erc20.in_redeem, true);
+            assert_eq!(erc20.redeem(10), Err(Error::RedeemFailed));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.total_supply(), 100);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_backing_token(None), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn supported_features_follow_the_configuration() {
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(
+                erc20.supported_features(),
+                vec![
+                    FeatureFlag::Pausable,
+                    FeatureFlag::Staking,
+                    FeatureFlag::Vesting
+                ]
+            );
+
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 100);
+            set_parameter_now(&mut erc20, ParamKey::CooldownBlocks, 5);
+            assert_eq!(erc20.set_whitelist_mode(true), Ok(()));
+            assert_eq!(
+                erc20.supported_features(),
+                vec![
+                    FeatureFlag::Fees,
+                    FeatureFlag::Pausable,
+                    FeatureFlag::Staking,
+                    FeatureFlag::Vesting,
+                    FeatureFlag::Whitelist,
+                    FeatureFlag::Cooldown
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn first_transfers_of_new_accounts_pay_no_fee() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 500), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            assert_eq!(erc20.set_free_transfers_for_new(2), Ok(()));
+            assert_eq!(erc20.free_transfers_for_new(), 2);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_free_transfers_for_new(5), Err(Error::NotOwner));
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 200);
+            assert_eq!(erc20.transfer_count(accounts.bob), 2);
+
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 290);
+            assert_eq!(erc20.balance_of(accounts.alice), 510);
+            assert_eq!(erc20.transfer_count(accounts.bob), 3);
+        }
+
+        #[ink::test]
+        fn sweep_expired_removes_only_expired_entries() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.create_vesting(accounts.bob, 10, 0, 4), Ok(()));
+            assert_eq!(erc20.create_vesting(accounts.charlie, 10, 0, 20), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.lock_until(3), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.lock_until(30), Ok(()));
+
+            advance_blocks(5);
+            assert_eq!(erc20.release(), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.release(), Ok(()));
+
+            set_caller(accounts.eve);
+            erc20.sweep_expired(vec![accounts.bob, accounts.charlie, accounts.django]);
+            assert_eq!(erc20.unlock_block_of(accounts.bob), 0);
+            assert_eq!(erc20.vesting_of(accounts.bob), None);
+            assert_eq!(erc20.unlock_block_of(accounts.charlie), 30);
+            assert!(erc20.vesting_of(accounts.charlie).is_some());
+            assert_eq!(erc20.storage_stats().vesting_count, 1);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded_event =
+                <Event as scale::Decode>::decode(&mut &emitted_events.last().unwrap().data[..])
+                    .expect("error decoding event data");
+            if let Event::LocksSwept(LocksSwept { count }) = decoded_event {
+                assert_eq!(count, 2);
+            } else {
+                panic!("invalid event type: expected a LocksSwept event")
+            }
+        }
+
+        #[ink::test]
+        fn max_single_approval_rejects_large_approvals() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.max_single_approval(), 0);
+            assert_eq!(erc20.approve(accounts.bob, 1_000_000), Ok(()));
+            assert_eq!(erc20.set_max_single_approval(50), Ok(()));
+
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(
+                erc20.approve(accounts.charlie, 51),
+                Err(Error::ApprovalTooLarge)
+            );
+            assert_eq!(
+                erc20.approve_with_label(accounts.charlie, 51, [0; 16]),
+                Err(Error::ApprovalTooLarge)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 0);
+            assert_eq!(erc20.approve(accounts.charlie, Balance::MAX), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_max_single_approval(0), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn approve_decaying_shrinks_linearly_to_zero() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                erc20.approve_decaying(accounts.bob, 100, 0),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.approve_decaying(accounts.bob, 100, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 100);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 30),
+                Ok(())
+            );
+            advance_blocks(2);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 70);
+            advance_blocks(3);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 51),
+                Err(Error::InsufficientApproval)
+            );
+            advance_blocks(3);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+            advance_blocks(2);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 1),
+                Err(Error::InsufficientApproval)
+            );
+
+            // a plain approval stops the decay
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.approve(accounts.bob, 5), Ok(()));
+            assert_eq!(erc20.allowance_decay(accounts.alice, accounts.bob), None);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 5);
+        }
+
+        #[ink::test]
+        fn get_past_total_supply_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.get_past_total_supply(0), Err(Error::InvalidParameter));
+
+            advance_blocks(2);
+            assert_eq!(erc20.mint(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.mint(accounts.bob, 100), Ok(()));
+            advance_blocks(3);
+            assert_eq!(erc20.burn(50), Ok(()));
+            advance_blocks(1);
+
+            assert_eq!(erc20.get_past_total_supply(0), Ok(1_000));
+            assert_eq!(erc20.get_past_total_supply(1), Ok(1_000));
+            assert_eq!(erc20.get_past_total_supply(2), Ok(1_200));
+            assert_eq!(erc20.get_past_total_supply(4), Ok(1_200));
+            assert_eq!(erc20.get_past_total_supply(5), Ok(1_150));
+            assert_eq!(erc20.get_past_total_supply(6), Err(Error::InvalidParameter));
+        }
+
+        #[ink::test]
+        fn require_opt_in_blocks_unsolicited_transfers() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_require_opt_in(true), Ok(()));
+            assert!(erc20.require_opt_in());
+
+            // the owner distributes without opt-in
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::RecipientNotOptedIn)
+            );
+
+            set_caller(accounts.charlie);
+            erc20.opt_in();
+            assert!(erc20.is_opted_in(accounts.charlie));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+
+            set_caller(accounts.charlie);
+            erc20.opt_out();
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::RecipientNotOptedIn)
+            );
+            assert_eq!(erc20.set_require_opt_in(false), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn allowances_for_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, 30), Ok(()));
+
+            assert_eq!(
+                erc20.allowances_for(
+                    accounts.alice,
+                    vec![accounts.bob, accounts.charlie, accounts.django]
+                ),
+                vec![10, 0, 30]
+            );
+            assert_eq!(
+                erc20.allowances_for(accounts.bob, vec![accounts.alice]),
+                vec![0]
+            );
+        }
+
+        #[ink::test]
+        fn fee_holidays_waive_fees_inside_the_windows() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 500), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            let now = ink_env::block_number::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get block number");
+            assert_eq!(erc20.schedule_fee_holiday(now + 2, now + 3), Ok(()));
+            assert_eq!(erc20.schedule_fee_holiday(now + 6, now + 6), Ok(()));
+            assert_eq!(
+                erc20.schedule_fee_holiday(now + 3, now + 5),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.schedule_fee_holiday(now + 5, now + 4),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.fee_holidays(),
+                vec![(now + 2, now + 3), (now + 6, now + 6)]
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.schedule_fee_holiday(now + 8, now + 9),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 90);
+            advance_blocks(2);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 190);
+            advance_blocks(1);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 290);
+            advance_blocks(1);
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 380);
+
+            // the windows over are dropped when scheduling
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.schedule_fee_holiday(now + 10, now + 12), Ok(()));
+            assert_eq!(
+                erc20.fee_holidays(),
+                vec![(now + 6, now + 6), (now + 10, now + 12)]
+            );
+        }
+
+        #[ink::test]
+        fn oracle_adjust_supply_works() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 900), Ok(()));
+            assert_eq!(
+                erc20.set_oracle(Some(accounts.django), 10_001),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.set_oracle(Some(accounts.django), 1_000), Ok(()));
+            assert_eq!(erc20.oracle(), (Some(accounts.django), 1_000));
+            assert_eq!(erc20.oracle_adjust_supply(1_100), Err(Error::NotOracle));
+
+            set_caller(accounts.django);
+            assert_eq!(
+                erc20.oracle_adjust_supply(1_101),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.oracle_adjust_supply(1_100), Ok(()));
+            assert_eq!(erc20.total_supply(), 1_100);
+            assert_eq!(erc20.balance_of(accounts.alice), 200);
+
+            // the fee recipient only holds 50 tokens to burn
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.transfer(accounts.bob, 150), Ok(()));
+            set_caller(accounts.django);
+            assert_eq!(
+                erc20.oracle_adjust_supply(1_000),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(erc20.oracle_adjust_supply(1_050), Ok(()));
+            assert_eq!(erc20.total_supply(), 1_050);
+            assert_eq!(erc20.balance_of(accounts.alice), 0);
+            assert_eq!(erc20.balance_of(accounts.bob), 1_050);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(&emitted_events[5], Some(accounts.alice), None, 50);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[6].data[..])
+                .expect("error decoding event data");
+            if let Event::SupplyAdjusted(SupplyAdjusted { from, to }) = decoded_event {
+                assert_eq!(from, 1_100);
+                assert_eq!(to, 1_050);
+            } else {
+                panic!("invalid event type: expected a SupplyAdjusted event")
+            }
+
+            // adjusting to the current supply is a no-op
+            let history = erc20.supply_history_page(0, 100);
+            assert_eq!(erc20.oracle_adjust_supply(1_050), Ok(()));
+            assert_eq!(erc20.supply_history_page(0, 100), history);
+            assert_eq!(
+                ink_env::test::recorded_events().count(),
+                emitted_events.len()
+            );
+        }
+
+        #[ink::test]
+        fn daily_limit_bounds_outbound_transfers() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            erc20.set_daily_limit(100);
+            assert_eq!(erc20.daily_limit(accounts.alice), (100, 0, 0));
+
+            assert_eq!(erc20.transfer(accounts.bob, 60), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 40), Ok(()));
+            assert_eq!(
+                erc20.transfer(accounts.bob, 1),
+                Err(Error::DailyLimitExceeded)
+            );
+            assert_eq!(erc20.daily_limit(accounts.alice), (100, 100, 0));
+
+            advance_blocks(DAY_BLOCKS);
+            assert_eq!(erc20.daily_limit(accounts.alice), (100, 0, DAY_BLOCKS));
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 200);
+        }
+
+        #[ink::test]
+        fn daily_limit_raises_after_a_delay() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            erc20.set_daily_limit(100);
+            erc20.set_daily_limit(500);
+            assert_eq!(erc20.daily_limit(accounts.alice).0, 100);
+            assert_eq!(
+                erc20.pending_daily_limit(accounts.alice),
+                Some((500, DAY_BLOCKS))
+            );
+            assert_eq!(
+                erc20.transfer(accounts.bob, 101),
+                Err(Error::DailyLimitExceeded)
+            );
+
+            // lowering applies right away and drops the pending raise
+            erc20.set_daily_limit(50);
+            assert_eq!(erc20.pending_daily_limit(accounts.alice), None);
+            erc20.set_daily_limit(500);
+
+            advance_blocks(DAY_BLOCKS);
+            assert_eq!(erc20.transfer(accounts.bob, 300), Ok(()));
+            assert_eq!(erc20.daily_limit(accounts.alice), (500, 300, DAY_BLOCKS));
+            assert_eq!(erc20.pending_daily_limit(accounts.alice), None);
+        }
+
+        #[ink::test]
+        fn whitelist_mode_lets_owner_distribute() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_whitelist_mode(true), Ok(()));
+
+            // neither Bob nor Charlie are whitelisted
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.mint(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 10),
+                Err(Error::NotWhitelisted)
+            );
+        }
+
+        #[ink::test]
+        fn new_with_namespace_tags_transfer_topics() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let namespace = *b"tokenabc";
+            let mut erc20 = Erc20::new_with_namespace(100, namespace);
+            assert_eq!(erc20.topic_namespace(), namespace);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_namespaced_transfer_event(
+                &emitted_events[0],
+                namespace,
+                None,
+                Some(accounts.alice),
+                100,
+            );
+            assert_namespaced_transfer_event(
+                &emitted_events[1],
+                namespace,
+                Some(accounts.alice),
+                Some(accounts.bob),
+                10,
+            );
+
+            // the namespace topic differs from a default deployment's
+            let default_topic = encoded_into_hash(&PrefixedValue {
+                prefix: b"Erc20::Transfer::namespace",
+                value: &[0u8; 8],
+            });
+            let topic = emitted_events[1].topics[2]
+                .decode::<Hash>()
+                .expect("invalid topic encoding");
+            assert_ne!(topic, default_topic);
+        }
+
+        #[ink::test]
+        fn require_spender_acceptance_blocks_unaccepted_spenders() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_require_spender_acceptance(true), Ok(()));
+            assert!(erc20.require_spender_acceptance());
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 10),
+                Err(Error::SpenderNotAccepted)
+            );
+            erc20.accept_spender_role(accounts.alice);
+            assert!(erc20.is_spender_accepted(accounts.alice, accounts.bob));
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 10),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+
+            erc20.renounce_spender_role(accounts.alice);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 10),
+                Err(Error::SpenderNotAccepted)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+            assert_eq!(
+                erc20.set_require_spender_acceptance(false),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn transfers_to_burn_addresses_burn() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            let dead = AccountId::from([0xde; 32]);
+            assert_eq!(erc20.set_burn_address(dead, true), Ok(()));
+            assert!(erc20.is_burn_address(dead));
+
+            assert_eq!(erc20.transfer(dead, 30), Ok(()));
+            assert_eq!(erc20.balance_of(dead), 0);
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.total_supply(), 70);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(&emitted_events[1], Some(accounts.alice), None, 30);
+
+            assert_eq!(erc20.set_burn_address(dead, false), Ok(()));
+            assert_eq!(erc20.transfer(dead, 10), Ok(()));
+            assert_eq!(erc20.balance_of(dead), 10);
+            assert_eq!(erc20.total_supply(), 70);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_burn_address(dead, true), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn pause_with_delay_gives_a_grace_period() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.pause_with_delay(3), Ok(()));
+            assert_eq!(erc20.scheduled_pause(), Some(3));
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("error decoding event data");
+            if let Event::PauseScheduled(PauseScheduled { at_block }) = decoded_event {
+                assert_eq!(at_block, 3);
+            } else {
+                panic!("invalid event type: expected a PauseScheduled event")
+            }
+
+            advance_blocks(2);
+            assert!(!erc20.paused());
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            advance_blocks(1);
+            assert!(erc20.paused());
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Paused));
+
+            assert_eq!(erc20.unpause(), Ok(()));
+            assert_eq!(erc20.scheduled_pause(), None);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.pause_with_delay(1), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn new_with_salt_records_the_salt() {
+            let erc20 = Erc20::new_with_salt(100, [7; 32]);
+            assert_eq!(erc20.deploy_salt(), [7; 32]);
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(Erc20::new(100).deploy_salt(), [0; 32]);
+        }
+
+        #[ink::test]
+        fn max_spenders_per_owner_bounds_new_spenders() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                erc20.max_spenders_per_owner(),
+                DEFAULT_MAX_SPENDERS_PER_OWNER
+            );
+            assert_eq!(erc20.set_max_spenders_per_owner(2), Ok(()));
 
-        type Event = <Erc20 as ::ink_lang::BaseEvent>::Type;
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 10), Ok(()));
+            assert_eq!(
+                erc20.approve(accounts.django, 10),
+                Err(Error::TooManySpenders)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.django), 0);
+            // existing spenders can still be updated
+            assert_eq!(erc20.approve(accounts.bob, 20), Ok(()));
+
+            assert_eq!(erc20.approve(accounts.charlie, 0), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.django), 10);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_max_spenders_per_owner(10), Err(Error::NotOwner));
+        }
 
         #[ink::test]
-        fn new_works() {
-            // Constructor works.
-            let _erc20 = Erc20::new(100);
+        fn spent_allowances_free_their_spender_slot() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_max_spenders_per_owner(1), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(
+                erc20.approve(accounts.charlie, 10),
+                Err(Error::TooManySpenders)
+            );
 
-            // Transfer event triggered during initial construction.
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(1, emitted_events.len());
+            // Bob spends his whole allowance
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Ok(())
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(erc20.approve(accounts.charlie, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn fee_brackets_charge_larger_transfers_more() {
+            let mut erc20 = Erc20::new(10_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(
+                erc20.set_fee_brackets(vec![(1_000, 500), (100, 100)]),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.set_fee_brackets(vec![(100, 10_001)]),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.set_fee_brackets(vec![(0, 100), (1_000, 500)]), Ok(()));
+            assert_eq!(erc20.fee_brackets(), vec![(0, 100), (1_000, 500)]);
+
+            assert_eq!(
+                erc20
+                    .preview_transfer(accounts.bob, accounts.charlie, 999)
+                    .fee,
+                9
+            );
+            assert_eq!(
+                erc20
+                    .preview_transfer(accounts.bob, accounts.charlie, 1_000)
+                    .fee,
+                50
+            );
+            assert_eq!(
+                erc20
+                    .preview_transfer(accounts.bob, accounts.charlie, 4_000)
+                    .fee,
+                200
+            );
+
+            assert_eq!(erc20.transfer(accounts.bob, 5_000), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 500), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 495);
+            assert_eq!(erc20.transfer(accounts.charlie, 2_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 2_395);
+            assert_eq!(erc20.set_fee_brackets(vec![]), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn guardian_recover_after_inactivity() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            set_caller(accounts.bob);
+            erc20.set_account_guardian(Some(accounts.charlie), 10);
+            assert_eq!(
+                erc20.account_guardian(accounts.bob),
+                Some((accounts.charlie, 10))
+            );
+
+            advance_blocks(5);
+            assert_eq!(erc20.transfer(accounts.eve, 10), Ok(()));
+            assert_eq!(erc20.last_activity_block(accounts.bob), 5);
+
+            set_caller(accounts.django);
+            assert_eq!(
+                erc20.guardian_recover(accounts.bob, accounts.django),
+                Err(Error::NotGuardian)
+            );
+            set_caller(accounts.charlie);
+            advance_blocks(9);
+            assert_eq!(
+                erc20.guardian_recover(accounts.bob, accounts.django),
+                Err(Error::RecoveryDelayActive)
+            );
+            advance_blocks(1);
+            assert_eq!(
+                erc20.guardian_recover(accounts.bob, accounts.django),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.django), 40);
+        }
+
+        #[ink::test]
+        fn treasury_ops_during_pause_let_fee_recipient_send() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.pause(), Ok(()));
+
+            // Alice is the fee recipient
+            assert_eq!(erc20.transfer(accounts.django, 10), Err(Error::Paused));
+            assert_eq!(erc20.set_treasury_ops_during_pause(true), Ok(()));
+            assert!(erc20.treasury_ops_during_pause());
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.django), 10);
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.django, 10), Err(Error::Paused));
+            assert_eq!(
+                erc20.set_treasury_ops_during_pause(false),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn revoke_vesting_pays_vested_and_returns_the_rest() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.create_vesting(accounts.bob, 20, 0, 10), Ok(()));
+            assert_eq!(
+                erc20.revoke_vesting(accounts.bob),
+                Err(Error::VestingNotRevocable)
+            );
+            assert_eq!(
+                erc20.revoke_vesting(accounts.charlie),
+                Err(Error::NoVesting)
+            );
+            assert_eq!(
+                erc20.create_revocable_vesting(accounts.charlie, 40, 0, 20),
+                Ok(())
+            );
+            assert!(erc20.vesting_of(accounts.charlie).unwrap().revocable);
+            assert_eq!(erc20.balance_of(accounts.alice), 40);
+
+            advance_blocks(5);
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.revoke_vesting(accounts.charlie), Err(Error::NotOwner));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
 
+            advance_blocks(5);
+            assert_eq!(erc20.revoke_vesting(accounts.charlie), Ok(()));
+            // half of 40 is vested, 10 released before and 10 paid on revocation
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.vesting_of(accounts.charlie), None);
+            assert_eq!(erc20.storage_stats().vesting_count, 1);
+
+            let contract = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get contract account");
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
             assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+                &emitted_events[emitted_events.len() - 2],
+                Some(contract),
+                Some(accounts.alice),
+                20,
             );
+            let event = emitted_events.last().unwrap();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::VestingRevoked(VestingRevoked {
+                beneficiary,
+                returned,
+            }) = decoded_event
+            {
+                assert_eq!(beneficiary, accounts.charlie);
+                assert_eq!(returned, 20);
+            } else {
+                panic!("invalid event type: expected a VestingRevoked event")
+            }
         }
 
-        /// The total supply was applied.
         #[ink::test]
-        fn total_supply_works() {
-            // Constructor works.
-            let erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
+        fn mint_proposal_executes_at_threshold() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.mint_threshold(), 1);
+            assert_eq!(erc20.set_mint_threshold(0), Err(Error::InvalidParameter));
+            assert_eq!(erc20.set_mint_threshold(2), Ok(()));
+            for minter in [accounts.bob, accounts.charlie, accounts.django] {
+                assert_eq!(erc20.grant_role(MINTER_ROLE, minter), Ok(()));
+            }
+            assert_eq!(
+                erc20.propose_mint(accounts.eve, 50),
+                Err(Error::MissingRole)
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.propose_mint(accounts.eve, 50), Ok(0));
+            assert_eq!(erc20.approve_mint(0), Err(Error::AlreadyApproved));
+            assert_eq!(erc20.approve_mint(1), Err(Error::MintProposalNotFound));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+            assert!(erc20.has_approved_mint(0, accounts.bob));
+            assert_eq!(
+                erc20.mint_proposal(0),
+                Some(MintProposal {
+                    to: accounts.eve,
+                    amount: 50,
+                    approvals: 1,
+                })
+            );
+
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.approve_mint(0), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.eve), 50);
+            assert_eq!(erc20.total_supply(), 150);
+            assert_eq!(erc20.mint_proposal(0), None);
+            // the approvals are dropped with the executed proposal
+            assert!(!erc20.has_approved_mint(0, accounts.bob));
+            assert!(!erc20.has_approved_mint(0, accounts.charlie));
+            assert!(erc20.mint_approvals.get(&0).is_none());
+
+            // executed proposals can't be approved again
+            set_caller(accounts.django);
+            assert_eq!(erc20.approve_mint(0), Err(Error::MintProposalNotFound));
+            ink_env::test::pop_execution_context();
+
             let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let event = emitted_events.last().unwrap();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::MintExecuted(MintExecuted { id }) = decoded_event {
+                assert_eq!(id, 0);
+            } else {
+                panic!("invalid event type: expected a MintExecuted event")
+            }
             assert_transfer_event(
-                &emitted_events[0],
+                &emitted_events[emitted_events.len() - 3],
                 None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+                Some(accounts.eve),
+                50,
             );
-            // Get the token total supply.
+        }
+
+        #[ink::test]
+        fn transfer_invoice_numbers_payments() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer_invoice(accounts.bob, 10), Ok(0));
+            advance_blocks(2);
+            assert_eq!(erc20.transfer_invoice(accounts.charlie, 20), Ok(1));
+            assert_eq!(
+                erc20.transfer_invoice(accounts.bob, 1000),
+                Err(Error::InsufficientBalance)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer_invoice(accounts.charlie, 5), Ok(2));
+            ink_env::test::pop_execution_context();
+
+            let invoices = ink_env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..])
+                        .expect("encountered invalid contract event data buffer")
+                    {
+                        Event::Invoice(Invoice {
+                            id,
+                            from,
+                            to,
+                            value,
+                            block,
+                        }) => Some((id, from, to, value, block)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                invoices,
+                vec![
+                    (0, accounts.alice, accounts.bob, 10, 0),
+                    (1, accounts.alice, accounts.charlie, 20, 2),
+                    (2, accounts.bob, accounts.charlie, 5, 2),
+                ]
+            );
+        }
+
+        #[test]
+        fn scale_decimals_works() {
+            // 1.5 tokens of 6 decimals wrapped into 18 decimals and back
+            assert_eq!(
+                scale_decimals(1_500_000, 6, 18),
+                Some(1_500_000_000_000_000_000)
+            );
+            assert_eq!(
+                scale_decimals(1_500_000_000_000_000_000, 18, 6),
+                Some(1_500_000)
+            );
+            // the dust below a base unit of 6 decimals is rounded down
+            assert_eq!(
+                scale_decimals(1_500_000_999_999_999_999, 18, 6),
+                Some(1_500_000)
+            );
+            assert_eq!(scale_decimals(999_999_999_999, 18, 6), Some(0));
+            assert_eq!(scale_decimals(42, 6, 6), Some(42));
+            assert_eq!(scale_decimals(Balance::MAX, 6, 18), None);
+        }
+
+        #[ink::test]
+        fn wrap_pulls_the_wrapped_token() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_wrapped_token(Some(accounts.django), 6), Ok(()));
+            assert_eq!(erc20.wrapped_token(), (Some(accounts.django), 6));
+
+            // `transfer_from()` pulls the deposit before anything is minted
+            assert_reaches_contract_call(|| {
+                let _ = erc20.wrap(1_000_000);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
             assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
         }
 
-        /// Get the actual balance of an account.
         #[ink::test]
-        fn balance_of_works() {
-            // Constructor works
-            let erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction
+        fn unwrap_pushes_the_wrapped_token() {
+            let mut erc20 = Erc20::new(2_000_000_000_000_000_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.set_wrapped_token(Some(accounts.django), 6), Ok(()));
+
+            // the caller is debited before `transfer()` pays the withdrawal,
+            // all but the dust not worth a base unit of the wrapped token
+            assert_reaches_contract_call(|| {
+                let _ = erc20.unwrap(1_000_000_000_000_000_005);
+            });
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000_000_000_000_000_000);
+            assert_eq!(erc20.total_supply(), 2_000_000_000_000_000_000);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn wrap_and_unwrap_fail_without_changes() {
+            let mut erc20 = Erc20::new(2_000_000_000_000_000_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.wrap(1_000_000), Err(Error::WrapFailed));
+            assert_eq!(erc20.unwrap(1_000), Err(Error::WrapFailed));
+            assert_eq!(
+                erc20.set_wrapped_token(Some(accounts.django), 39),
+                Err(Error::InvalidParameter)
+            );
+
+            assert_eq!(erc20.set_wrapped_token(Some(accounts.django), 6), Ok(()));
+            assert_eq!(erc20.wrap(0), Err(Error::InvalidParameter));
+            // less than a base unit of the wrapped token
+            assert_eq!(erc20.unwrap(999_999_999_999), Err(Error::InvalidParameter));
+            assert_eq!(
+                erc20.unwrap(3_000_000_000_000_000_000),
+                Err(Error::InsufficientBalance)
+            );
+            Lazy::set(&mut erc20.cap, 2_000_000_000_000_000_000);
+            assert_eq!(erc20.wrap(1), Err(Error::CapExceeded));
+            assert_eq!(erc20.balance_of(accounts.alice), 2_000_000_000_000_000_000);
+            assert_eq!(erc20.total_supply(), 2_000_000_000_000_000_000);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_wrapped_token(None, 6), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn staking_pause_is_independent_of_transfer_pause() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.stake(30), Ok(()));
+            assert_eq!(erc20.set_staking_paused(true), Ok(()));
+            assert!(erc20.staking_paused());
+            assert_eq!(erc20.stake(10), Err(Error::StakingPaused));
+            assert_eq!(erc20.unstake(10), Err(Error::StakingPaused));
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.bob, 10, 5_000),
+                Err(Error::StakingPaused)
+            );
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.staked_balance_of(accounts.alice), 30);
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+
+            // resuming staking leaves transfers alone, but the global pause
+            // halts both
+            assert_eq!(erc20.set_staking_paused(false), Ok(()));
+            assert_eq!(erc20.pause(), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(erc20.stake(10), Err(Error::Paused));
+            assert_eq!(erc20.unpause(), Ok(()));
+            assert_eq!(erc20.stake(10), Ok(()));
+            assert_eq!(erc20.unstake(40), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 90);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_staking_paused(true), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn convert_burns_v1_and_mints_v2() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.convert(10), Err(Error::ConversionDisabled));
+            assert_eq!(erc20.set_conversion_ratio(2), Ok(()));
+            assert_eq!(erc20.conversion_ratio(), 2);
+            assert_eq!(erc20.convert(101), Err(Error::InsufficientBalance));
+
+            assert_eq!(erc20.convert(30), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.total_supply(), 70);
+            assert_eq!(erc20.v2_balance_of(accounts.alice), 60);
+            assert_eq!(erc20.v2_total_supply(), 60);
+
             let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
             assert_transfer_event(
-                &emitted_events[0],
+                &emitted_events[emitted_events.len() - 2],
+                Some(accounts.alice),
                 None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+                30,
             );
+            let event = emitted_events.last().unwrap();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::Converted(Converted {
+                who,
+                burned,
+                minted,
+            }) = decoded_event
+            {
+                assert_eq!(who, accounts.alice);
+                assert_eq!(burned, 30);
+                assert_eq!(minted, 60);
+            } else {
+                panic!("invalid event type: expected a Converted event")
+            }
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.set_conversion_ratio(3), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn queued_transfers_expire_after_the_window() {
+            let mut erc20 = Erc20::new(100);
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
-            // Alice owns all the tokens on deployment
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
-            // Bob does not owns tokens
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.set_execution_window_blocks(3), Ok(()));
+            assert_eq!(erc20.execution_window_blocks(), 3);
+            assert_eq!(erc20.queue_transfer(accounts.bob, 30, 2), Ok(0));
+            assert_eq!(erc20.queue_transfer(accounts.charlie, 20, 2), Ok(1));
+            assert_eq!(
+                erc20.queue_transfer(accounts.bob, 51, 2),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 50);
+            assert_eq!(erc20.execute_queued_transfer(0), Err(Error::TimelockActive));
+
+            // within the window
+            advance_blocks(4);
+            assert_eq!(erc20.execute_queued_transfer(0), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+            assert_eq!(erc20.queued_transfer(0), None);
+            assert_eq!(
+                erc20.execute_queued_transfer(0),
+                Err(Error::QueuedTransferNotFound)
+            );
+            assert_eq!(erc20.cleanup_expired(1), Err(Error::TransferNotExpired));
+
+            // past the window
+            advance_blocks(2);
+            assert_eq!(
+                erc20.execute_queued_transfer(1),
+                Err(Error::TransferExpired)
+            );
+            set_caller(accounts.django);
+            assert_eq!(erc20.cleanup_expired(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.balance_of(accounts.charlie), 0);
+            assert_eq!(erc20.cleanup_expired(1), Err(Error::QueuedTransferNotFound));
+
+            let contract = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get contract account");
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+            assert_transfer_event(&emitted_events[1], Some(accounts.alice), Some(contract), 30);
+            assert_transfer_event(&emitted_events[2], Some(accounts.alice), Some(contract), 20);
+            assert_transfer_event(&emitted_events[3], Some(contract), Some(accounts.bob), 30);
+            assert_transfer_event(&emitted_events[4], Some(contract), Some(accounts.alice), 20);
+        }
+
+        #[ink::test]
+        fn queued_transfers_respect_the_transfer_checks_and_fees() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.django, BlockNumber::MAX),
+                Ok(())
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.queue_transfer(accounts.django, 10, 0),
+                Err(Error::Blacklisted)
+            );
+            assert_eq!(erc20.queue_transfer(accounts.charlie, 20, 0), Ok(0));
+            assert_eq!(erc20.queue_transfer(accounts.charlie, 10, 0), Ok(1));
+            ink_env::test::pop_execution_context();
+
+            // the checks run again on execution
+            assert_eq!(erc20.pause(), Ok(()));
+            assert_eq!(erc20.execute_queued_transfer(0), Err(Error::Paused));
+            assert_eq!(erc20.unpause(), Ok(()));
+            assert_eq!(
+                erc20.add_to_blacklist(accounts.charlie, BlockNumber::MAX),
+                Ok(())
+            );
+            assert_eq!(erc20.execute_queued_transfer(1), Err(Error::Blacklisted));
+            assert_eq!(erc20.remove_from_blacklist(accounts.charlie), Ok(()));
+
+            // Bob pays the 10% fee on execution
+            assert_eq!(erc20.execute_queued_transfer(0), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 18);
+            assert_eq!(erc20.balance_of(accounts.alice), 52);
+            assert_eq!(erc20.balance_of(accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn parameter_changes_wait_for_the_delay() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.parameter_delay_blocks(), MIN_PARAMETER_DELAY_BLOCKS);
+            assert_eq!(
+                erc20.set_parameter_delay_blocks(MIN_PARAMETER_DELAY_BLOCKS - 1),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.set_parameter_delay_blocks(MAX_PARAMETER_DELAY_BLOCKS + 1),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(
+                erc20.set_parameter_delay_blocks(BlockNumber::MAX),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.set_parameter_delay_blocks(700), Ok(()));
+            assert_eq!(erc20.parameter_delay_blocks(), 700);
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(
+                erc20.set_parameter(ParamKey::FeeBps, 10_001),
+                Err(Error::InvalidParameter)
+            );
+            assert_eq!(erc20.set_parameter(ParamKey::FeeBps, 500), Ok(()));
+            // overwritten before it takes effect
+            assert_eq!(erc20.set_parameter(ParamKey::FeeBps, 1_000), Ok(()));
+            assert_eq!(
+                erc20.pending_parameter(ParamKey::FeeBps),
+                Some((1_000, 700))
+            );
+            assert_eq!(
+                erc20.apply_parameter(ParamKey::FeeBps),
+                Err(Error::TimelockActive)
+            );
+            assert_eq!(
+                erc20.apply_parameter(ParamKey::BurnBps),
+                Err(Error::NoPendingParameter)
+            );
+
+            // the old fee applies before the delay
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+
+            advance_blocks(700);
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 0);
+            assert_eq!(erc20.apply_parameter(ParamKey::FeeBps), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.parameter(ParamKey::FeeBps), 1_000);
+            assert_eq!(erc20.pending_parameter(ParamKey::FeeBps), None);
+
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 19);
+            assert_eq!(
+                erc20.set_parameter_delay_blocks(700),
+                Err(Error::NotGovernor)
+            );
         }
 
         #[ink::test]
-        fn transfer_works() {
-            // Constructor works.
-            let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
+        fn tax_burn_share_splits_the_fee_after_launch() {
+            let mut erc20 = Erc20::new(10_000);
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 5_000), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            set_parameter_now(&mut erc20, ParamKey::TaxBurnShareBps, 5_000);
+            // no sniper tax window
+            assert_eq!(erc20.sniper_tax(), (0, 0));
 
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-            // Alice transfers 10 tokens to Bob.
-            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
-            // Bob owns 10 tokens.
-            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            // half of the 100 tokens fee is burned, half goes to the treasury
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 1_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 900);
+            assert_eq!(erc20.balance_of(accounts.alice), 5_050);
+            assert_eq!(erc20.total_supply(), 9_950);
 
             let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 2);
-            // Check first transfer event related to ERC-20 instantiation.
+            let len = emitted_events.len();
             assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
-            // Check the second transfer event relating to the actual trasfer.
-            assert_transfer_event(
-                &emitted_events[1],
-                Some(AccountId::from([0x01; 32])),
-                Some(AccountId::from([0x02; 32])),
-                10,
+                &emitted_events[len - 2],
+                Some(accounts.bob),
+                Some(accounts.alice),
+                50,
             );
+            assert_transfer_event(&emitted_events[len - 1], Some(accounts.bob), None, 50);
         }
 
         #[ink::test]
-        fn transfer_fails_with_insufficient_balance() {
-            // Constructor works.
-            let mut erc20 = Erc20::new(100);
+        fn reflection_tax_grows_the_holders_balances() {
+            let mut erc20 = Erc20::new_with_cap(1_000, 1_000_000, 0, 0);
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
+            // Alice is the treasury and Django an AMM pair
+            assert_eq!(erc20.set_reflection_excluded(accounts.alice, true), Ok(()));
+            assert_eq!(erc20.set_reflection_excluded(accounts.django, true), Ok(()));
+            assert!(erc20.is_reflection_excluded(accounts.django));
+            assert_eq!(erc20.transfer(accounts.bob, 370), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 400), Ok(()));
+            assert_eq!(erc20.transfer(accounts.django, 100), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::ReflectionBps, 1_000);
+            assert_eq!(erc20.parameter(ParamKey::ReflectionBps), 1_000);
 
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call
-            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
+            // 10% of the 70 tokens are shared by Bob and Charlie
+            assert_eq!(
+                erc20.preview_transfer(accounts.bob, accounts.django, 70),
+                TransferPreview {
+                    net_to_recipient: 63,
+                    fee: 0,
+                    burn: 0,
+                    tax: 0,
+                    reflection: 7,
+                }
             );
-
-            // Bob fails to transfers 10 tokens to Eve, note the caller is now bob
+            set_caller(accounts.bob);
             assert_eq!(
-                erc20.transfer(accounts.eve, 10),
-                Err(Error::InsufficientBalance)
+                erc20.set_reflection_excluded(accounts.bob, true),
+                Err(Error::NotOwner)
             );
-            // Alice owns all the tokens.
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-            assert_eq!(erc20.balance_of(accounts.eve), 0);
+            assert_eq!(erc20.transfer(accounts.django, 70), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 303);
+            assert_eq!(erc20.balance_of(accounts.charlie), 404);
+
+            // the excluded accounts only get what they are sent
+            assert_eq!(erc20.balance_of(accounts.alice), 130);
+            assert_eq!(erc20.balance_of(accounts.django), 163);
+            assert_eq!(erc20.total_supply(), 1_000);
 
-            // Transfer event triggered during initial construction.
             let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 1);
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+            let decoded_event = <Event as scale::Decode>::decode(
+                &mut &emitted_events[emitted_events.len() - 1].data[..],
+            )
+            .expect("error decoding event data");
+            if let Event::Reflected(Reflected { from, value }) = decoded_event {
+                assert_eq!(from, accounts.bob);
+                assert_eq!(value, 7);
+            } else {
+                panic!("invalid event type: expected a Reflected event")
+            }
+
+            // taking part in reflections again keeps the balance
+            assert_eq!(
+                erc20.set_reflection_excluded(accounts.django, false),
+                Ok(())
             );
+            assert!(!erc20.is_reflection_excluded(accounts.django));
+            assert_eq!(erc20.balance_of(accounts.django), 163);
+            assert_eq!(erc20.balance_of(accounts.bob), 303);
+            assert_eq!(erc20.balance_of(accounts.charlie), 404);
+            assert_eq!(130 + 163 + 303 + 404, erc20.total_supply());
         }
 
         #[ink::test]
-        fn transfer_from_works() {
-            // Constructor works.
-            let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
+        fn reflections_keep_small_credits() {
+            let mut erc20 = Erc20::new_with_cap(1_000, 1_000_000, 0, 0);
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
+            let holders = [
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+                accounts.eve,
+            ];
+            let sum_of_balances = |erc20: &Erc20| {
+                holders
+                    .iter()
+                    .map(|who| erc20.balance_of(*who))
+                    .sum::<Balance>()
+            };
+            // Alice is the treasury and Django an AMM pair
+            assert_eq!(erc20.set_reflection_excluded(accounts.alice, true), Ok(()));
+            assert_eq!(erc20.set_reflection_excluded(accounts.django, true), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 300), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 400), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::ReflectionBps, 1_000);
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.transfer(accounts.django, 100), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 305);
+            assert_eq!(erc20.balance_of(accounts.charlie), 305);
+            assert_eq!(sum_of_balances(&erc20), erc20.total_supply());
 
-            // Bob fails to transfer tokens owned by Alice.
+            // single tokens sent after the reflection aren't rounded away
+            set_caller(accounts.bob);
+            for _ in 0..5 {
+                assert_eq!(erc20.transfer(accounts.eve, 1), Ok(()));
+            }
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.eve), 5);
+            assert_eq!(erc20.balance_of(accounts.bob), 300);
             assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 10),
-                Err(Error::InsufficientApproval)
+                erc20.transfer_and_stake(accounts.eve, 1, MAX_BPS as u16),
+                Ok(())
+            );
+            assert_eq!(erc20.staked_balance_of(accounts.eve), 1);
+            assert_eq!(
+                sum_of_balances(&erc20) + erc20.total_staked(),
+                erc20.total_supply()
             );
-            // Alice approves Bob for token transfers on her behalf.
-            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
-
-            // The approve event takes place.
-            assert_eq!(ink_env::test::recorded_events().count(), 2);
 
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call.
-            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller.
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
+            // an uncapped token can't reflect
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(
+                erc20.set_parameter(ParamKey::ReflectionBps, 1_000),
+                Err(Error::InvalidParameter)
             );
+        }
 
-            // Bob transfers tokens from Alice to Eve.
+        #[ink::test]
+        fn unchecked_transfer_only_by_trusted_callers() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_trusted_caller(accounts.django, true), Ok(()));
+            assert!(erc20.is_trusted_caller(accounts.django));
+
+            set_caller(accounts.bob);
             assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 10),
-                Ok(())
+                erc20.set_trusted_caller(accounts.bob, true),
+                Err(Error::NotOwner)
             );
-            // Eve owns tokens.
-            assert_eq!(erc20.balance_of(accounts.eve), 10);
+            assert_eq!(
+                erc20.unchecked_transfer(accounts.charlie, 10),
+                Err(Error::NotTrusted)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
 
-            // Check all transfer events that happened during the previous calls:
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 3);
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+            // Bob's tokens are forwarded by the trusted router Django
+            set_caller(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.django, 30), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_caller(accounts.django);
+            assert_eq!(erc20.unchecked_transfer(accounts.charlie, 30), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.django), 0);
+            assert_eq!(erc20.balance_of(accounts.charlie), 30);
+
+            // the checks still apply
+            set_caller(accounts.django);
+            assert_eq!(
+                erc20.unchecked_transfer(accounts.charlie, 1),
+                Err(Error::InsufficientBalance)
             );
-            // The second event `emitted_events[1]` is an Approve event that we skip checking.
-            assert_transfer_event(
-                &emitted_events[2],
-                Some(AccountId::from([0x01; 32])),
-                Some(AccountId::from([0x05; 32])),
-                10,
+            ink_env::test::pop_execution_context();
+
+            assert_eq!(erc20.set_trusted_caller(accounts.django, false), Ok(()));
+            set_caller(accounts.django);
+            assert_eq!(
+                erc20.unchecked_transfer(accounts.charlie, 0),
+                Err(Error::NotTrusted)
             );
         }
 
         #[ink::test]
-        fn transfer_from_fails_with_insufficient_allowance() {
-            // Constructor works.
+        fn reentrancy_guard_blocks_reentered_transfers() {
             let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
+            assert_eq!(erc20.approve(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.set_trusted_caller(accounts.django, true), Ok(()));
+            assert_eq!(erc20.transfer(accounts.django, 20), Ok(()));
+            // the guard is released once a transfer is done
+            assert!(!erc20.is_locked());
 
-            // Alice approves Bob for maximal 10 tokens transfer
-            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
-
-            // The approve event takes place.
-            assert_eq!(ink_env::test::recorded_events().count(), 2);
+            // as seen by an observer called from within a transfer
+            Lazy::set(&mut erc20.entered, true);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Reentrancy));
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::Reentrancy)
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
 
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call.
-            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller.
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
+            // nor can the other messages making external calls be entered
+            assert_eq!(
+                erc20.split_transfer(10, vec![(accounts.bob, MAX_BPS as u16)]),
+                Err(Error::Reentrancy)
+            );
+            assert_eq!(
+                erc20.batch_transfer(vec![(accounts.bob, 10)]),
+                Err(Error::Reentrancy)
+            );
+            assert_eq!(
+                erc20.transfer_and_stake(accounts.bob, 10, 0),
+                Err(Error::Reentrancy)
+            );
+            assert_eq!(erc20.create_claim(accounts.bob, 10), Err(Error::Reentrancy));
+            assert_eq!(
+                erc20.queue_transfer(accounts.bob, 10, 0),
+                Err(Error::Reentrancy)
+            );
+            assert_eq!(erc20.burn(10), Err(Error::Reentrancy));
+            assert_eq!(
+                erc20.approve_call_before(accounts.bob, 10, Vec::new(), 10),
+                Err(Error::Reentrancy)
             );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+            assert_eq!(erc20.balance_of(accounts.alice), 80);
 
-            // Bob tries to transfer more than he is allowed (20 > 10)
+            // a trusted caller gets through, as does the router of a fee swap
+            set_caller(accounts.django);
+            assert_eq!(erc20.unchecked_transfer(accounts.charlie, 10), Ok(()));
+            ink_env::test::pop_execution_context();
+            // as the router sees it while swapping the fees
+            Lazy::set(&mut erc20.reentrant_callee, Some(accounts.eve));
+            set_caller(accounts.bob);
             assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 20),
-                Err(Error::InsufficientApproval)
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::Reentrancy)
             );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.approve(accounts.eve, 10), Ok(()));
+            set_caller(accounts.eve);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Ok(())
+            );
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+            // still held for the transfer running the swap
+            assert!(erc20.is_locked());
+        }
 
-            // Alice's balance is unchanged
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
+        #[test]
+        fn escrow_messages_are_dispatchable() {
+            // the metadata ink! generates for the contract's ABI
+            extern "Rust" {
+                #[link_name = "__ink_generate_metadata"]
+                fn generate_metadata() -> ink_metadata::InkProject;
+            }
+            // the escrowed tokens can only leave through these messages
+            let metadata = unsafe { generate_metadata() };
+            let messages: Vec<String> = metadata
+                .spec()
+                .messages()
+                .iter()
+                .map(|message| message.name().join("::"))
+                .collect();
+            for name in [
+                "create_claim",
+                "claim",
+                "reclaim",
+                "queue_transfer",
+                "execute_queued_transfer",
+                "cleanup_expired",
+            ] {
+                assert!(
+                    messages.iter().any(|message| message == name),
+                    "{} isn't a message",
+                    name
+                );
+            }
+        }
 
-            // Eve doesn't have any tokens
-            assert_eq!(erc20.balance_of(accounts.eve), 0);
+        #[ink::test]
+        fn is_locked_reads_false_outside_any_call() {
+            let erc20 = Erc20::new(100);
+            assert!(!erc20.is_locked());
         }
+        #[ink::test]
+        fn batch_transfer_charges_the_min_fee_once() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            set_parameter_now(&mut erc20, ParamKey::FeeBps, 1_000);
+            set_parameter_now(&mut erc20, ParamKey::MinFee, 5);
 
+            // a min fee per recipient would take all of their 3 tokens
+            let recipients: Vec<AccountId> = (1..=10u8)
+                .map(|i| AccountId::from([i + 0x10; 32]))
+                .collect();
+            set_caller(accounts.bob);
+            assert_eq!(erc20.airdrop_equal(recipients.clone(), 3), Ok(()));
+            let received: Vec<Balance> =
+                recipients.iter().map(|to| erc20.balance_of(*to)).collect();
+            assert_eq!(received, vec![2, 2, 2, 2, 2, 3, 3, 3, 3, 3]);
+            assert_eq!(received.iter().sum::<Balance>(), 25);
+            assert_eq!(erc20.balance_of(accounts.alice), 905);
+            assert_eq!(erc20.balance_of(accounts.bob), 70);
+        }
         #[ink::test]
-        fn allowance_must_not_change_on_failed_transfer() {
+        fn mint_approvals_are_dropped_once_executed() {
             let mut erc20 = Erc20::new(100);
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
+            assert_eq!(erc20.set_mint_threshold(3), Ok(()));
+            for minter in [accounts.bob, accounts.charlie, accounts.django] {
+                assert_eq!(erc20.grant_role(MINTER_ROLE, minter), Ok(()));
+            }
 
-            // Alice approves Bob for token transfers on her behalf.
-            let alice_balance = erc20.balance_of(accounts.alice);
-            let initial_allowance = alice_balance + 2;
-            assert_eq!(erc20.approve(accounts.bob, initial_allowance), Ok(()));
-
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call.
-            let mut data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller.
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
-
-            // Bob tries to transfer tokens from Alice to Eve.
-            let emitted_events_before = ink_env::test::recorded_events();
+            set_caller(accounts.bob);
+            assert_eq!(erc20.propose_mint(accounts.eve, 50), Ok(0));
+            ink_env::test::pop_execution_context();
+            set_caller(accounts.charlie);
+            assert_eq!(erc20.approve_mint(0), Ok(()));
+            assert_eq!(erc20.approve_mint(0), Err(Error::AlreadyApproved));
+            ink_env::test::pop_execution_context();
             assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, alice_balance + 1),
-                Err(Error::InsufficientBalance)
+                erc20.mint_approvals.get(&0),
+                Some(&vec![accounts.bob, accounts.charlie])
             );
-            // Allowance must have stayed the same
+
+            set_caller(accounts.django);
+            assert_eq!(erc20.approve_mint(0), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc20.balance_of(accounts.eve), 50);
+            assert!(erc20.mint_approvals.get(&0).is_none());
+        }
+        #[ink::test]
+        fn revoke_vesting_of_a_fully_vested_schedule() {
+            let mut erc20 = Erc20::new(100);
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
             assert_eq!(
-                erc20.allowance(accounts.alice, accounts.bob),
-                initial_allowance
+                erc20.create_revocable_vesting(accounts.charlie, 40, 0, 20),
+                Ok(())
             );
-            // No more events must have been emitted
-            let emitted_events_after = ink_env::test::recorded_events();
-            assert_eq!(emitted_events_before.count(), emitted_events_after.count());
+
+            advance_blocks(20);
+            assert_eq!(erc20.revoke_vesting(accounts.charlie), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 40);
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.vesting_of(accounts.charlie), None);
+
+            // nothing is returned, so no `Transfer` follows the release
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded_event = <Event as scale::Decode>::decode(
+                &mut &emitted_events[emitted_events.len() - 2].data[..],
+            )
+            .expect("encountered invalid contract event data buffer");
+            assert!(matches!(decoded_event, Event::VestingReleased(_)));
+            let event = emitted_events.last().unwrap();
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::VestingRevoked(VestingRevoked { returned, .. }) = decoded_event {
+                assert_eq!(returned, 0);
+            } else {
+                panic!("invalid event type: expected a VestingRevoked event")
+            }
         }
 
         /// common assertion that are used in multiple unittests
@@ -451,13 +10547,36 @@ mod erc20 {
             expected_from: Option<AccountId>,
             expected_to: Option<AccountId>,
             expected_value: Balance,
+        ) {
+            assert_namespaced_transfer_event(
+                event,
+                [0; 8],
+                expected_from,
+                expected_to,
+                expected_value,
+            )
+        }
+
+        fn assert_namespaced_transfer_event(
+            event: &ink_env::test::EmittedEvent,
+            expected_namespace: [u8; 8],
+            expected_from: Option<AccountId>,
+            expected_to: Option<AccountId>,
+            expected_value: Balance,
         ) {
             let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
                 .expect("error decoding event data");
-            if let Event::Transfer(Transfer { from, to, value }) = decoded_event {
+            if let Event::Transfer(Transfer {
+                from,
+                to,
+                value,
+                namespace,
+            }) = decoded_event
+            {
                 assert_eq!(from, expected_from, "invalid Transfer.from");
                 assert_eq!(to, expected_to, "invalid Transfer.to");
                 assert_eq!(value, expected_value, "invalid Trasfer.value");
+                assert_eq!(namespace, expected_namespace, "invalid Transfer.namespace");
             } else {
                 panic!("invalid event type: expected a Transfer event")
             }
@@ -471,14 +10590,11 @@ mod erc20 {
                     value: &expected_from,
                 }),
                 encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::to",
-                    value: &expected_to,
-                }),
-                encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::value",
-                    value: &expected_value,
+                    prefix: b"Erc20::Transfer::namespace",
+                    value: &expected_namespace,
                 }),
             ];
+            assert_eq!(event.topics.len(), expected_topics.len());
             for (n, (actual_topic, expected_topic)) in
                 event.topics.iter().zip(expected_topics).enumerate()
             {
@@ -489,6 +10605,49 @@ mod erc20 {
             }
         }
 
+        /// set `caller` as the caller of the subsequent contract calls
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or_else(|_| [0x0; 32].into());
+            let data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, 1000000, data,
+            );
+        }
+
+        /// run `call` and check it stopped at a cross-contract call, which
+        /// the off-chain environment can't make
+        fn assert_reaches_contract_call<F: FnOnce()>(call: F) {
+            let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(call))
+                .expect_err("expected a cross-contract call");
+            let message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or_default();
+            assert!(
+                message.contains("off-chain environment does not support contract"),
+                "unexpected panic: {}",
+                message
+            );
+        }
+
+        /// advance the off-chain block number by `n` blocks
+        fn advance_blocks(n: u32) {
+            for _ in 0..n {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+        }
+
+        /// queue setting the governed parameter `key` to `value` and apply it
+        /// once the parameter delay passed
+        fn set_parameter_now(erc20: &mut Erc20, key: ParamKey, value: u128) {
+            assert_eq!(erc20.set_parameter(key, value), Ok(()));
+            advance_blocks(erc20.parameter_delay_blocks());
+            assert_eq!(erc20.apply_parameter(key), Ok(()));
+        }
+
         /// For calculating the event topic hash.
         struct PrefixedValue<'a, 'b, T> {
             pub prefix: &'a [u8],